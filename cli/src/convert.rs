@@ -0,0 +1,343 @@
+//! `sqlx-named convert`
+
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use sqlparser::tokenizer::Token;
+use sqlx_named_core::{
+    args::{get_name, pun_name},
+    config::Config,
+    sql,
+};
+use syn::{
+    parse::{ParseStream, Parser as _},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit::Visit,
+    Expr, LitStr, Macro, Token, Type,
+};
+
+use crate::files;
+
+const INLINE_MACROS: [&str; 6] = [
+    "query",
+    "query_as",
+    "query_scalar",
+    "query_unchecked",
+    "query_as_unchecked",
+    "query_scalar_unchecked",
+];
+
+/// A replacement of a byte range of the source
+struct Edit {
+    range: Range<usize>,
+    text: String,
+}
+
+/// Gives each arg the name it's inferred to have, or `argN` when it's unknown or already taken
+fn arg_names(args: &Punctuated<Expr, Token![,]>) -> Vec<String> {
+    let mut names = Vec::<Option<String>>::new();
+    for arg in args {
+        let name = pun_name(arg)
+            .or_else(|| get_name(arg))
+            .filter(|name| !names.contains(&Some(name.clone())));
+        names.push(name);
+    }
+
+    // the fallbacks can't take the name of another arg, even one that comes after them
+    let mut taken = names.iter().flatten().cloned().collect::<Vec<_>>();
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| {
+            name.unwrap_or_else(|| {
+                let name = (index + 1..)
+                    .map(|n| format!("arg{}", n))
+                    .find(|name| !taken.contains(name))
+                    .unwrap();
+                taken.push(name.clone());
+                name
+            })
+        })
+        .collect()
+}
+
+/// The edits of a query replacing its positional placeholders with the names of their args,
+/// leaving the rest of it as written
+fn rename_placeholders(sql: &str, names: &[String], config: &Config) -> Result<Vec<Edit>, String> {
+    let tokens = sql::tokenize_located(sql, &*config.dialect.tokenizer_dialect(), config.sigil)
+        .map_err(|err| err.to_string())?;
+
+    let mut edits = Vec::new();
+    let mut next = 0;
+    for (token, range) in tokens {
+        let Token::Placeholder(placeholder) = token else {
+            continue;
+        };
+        if !sql::is_positional(&placeholder) {
+            return Err(format!(
+                "already uses the named placeholder `{}`",
+                placeholder
+            ));
+        }
+        // mysql's `?` takes the next arg
+        let position = if placeholder == "?" {
+            next += 1;
+            next
+        } else {
+            placeholder[1..].parse::<usize>().unwrap()
+        };
+        let name = position
+            .checked_sub(1)
+            .and_then(|index| names.get(index))
+            .ok_or_else(|| format!("no arg given for `{}`", placeholder))?;
+        edits.push(Edit {
+            range,
+            text: format!("{}{}", config.sigil, name),
+        });
+    }
+
+    Ok(edits)
+}
+
+/// The offset in the source of a string literal of each byte of its value, and of the end of
+/// the value, so that the value can be edited in place, escapes included
+fn value_offsets(literal: &str) -> Vec<usize> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let start = raw.find('"').unwrap_or_default() + 2;
+        let end = literal.rfind('"').unwrap_or(start);
+        return (start..=end).collect();
+    }
+
+    let mut offsets = Vec::new();
+    let mut chars = literal.char_indices().skip(1).peekable();
+    while let Some((offset, c)) = chars.next() {
+        let len = match c {
+            '"' => {
+                offsets.push(offset);
+                break;
+            }
+            '\\' => match chars.next().map_or(' ', |(_, escape)| escape) {
+                // a line continuation skips the following whitespace
+                '\n' | '\r' => {
+                    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+                    0
+                }
+                'x' => {
+                    chars.nth(1);
+                    1
+                }
+                'u' => {
+                    let mut hex = String::new();
+                    while let Some((_, c)) = chars.next_if(|&(_, c)| c != '}') {
+                        hex.push(c);
+                    }
+                    chars.next();
+                    u32::from_str_radix(hex.trim_start_matches('{'), 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .map_or(1, char::len_utf8)
+                }
+                _ => 1,
+            },
+            '\r' if chars.next_if(|&(_, c)| c == '\n').is_some() => 1,
+            c => c.len_utf8(),
+        };
+        offsets.extend(std::iter::repeat_n(offset, len));
+    }
+    offsets
+}
+
+/// What happened to a `sqlx` query macro
+struct Note {
+    line: usize,
+    macro_name: String,
+    /// Why it was left as is
+    skipped: Option<String>,
+}
+
+struct Converter<'a> {
+    src: &'a str,
+    config: &'a Config,
+    edits: Vec<Edit>,
+    notes: Vec<Note>,
+}
+
+impl Converter<'_> {
+    fn convert(&self, mac: &Macro, macro_name: &str) -> Result<Vec<Edit>, String> {
+        if !INLINE_MACROS.contains(&macro_name) {
+            return Err("query files have to be converted by hand".to_owned());
+        }
+
+        let as_type = macro_name.starts_with("query_as");
+        let parser = |input: ParseStream| {
+            if as_type {
+                input.parse::<Type>()?;
+                input.parse::<Token![,]>()?;
+            }
+            let sql = input.parse::<LitStr>()?;
+            let args = if input.is_empty() {
+                Punctuated::new()
+            } else {
+                input.parse::<Token![,]>()?;
+                Punctuated::<Expr, Token![,]>::parse_terminated(input)?
+            };
+            Ok((sql, args))
+        };
+        let (lit, args) = parser
+            .parse2(mac.tokens.clone())
+            .map_err(|err| err.to_string())?;
+
+        let names = arg_names(&args);
+        let sql = lit.value();
+        let renames = rename_placeholders(&sql, &names, self.config)?;
+
+        let literal = lit.span().byte_range();
+        let offsets = value_offsets(&self.src[literal.clone()]);
+        if offsets.len() != sql.len() + 1 {
+            return Err("failed to locate the query in its string literal".to_owned());
+        }
+
+        let mut edits = vec![Edit {
+            range: mac.path.segments[0].ident.span().byte_range(),
+            text: "sqlx_named".to_owned(),
+        }];
+        edits.extend(renames.into_iter().map(|edit| Edit {
+            range: literal.start + offsets[edit.range.start]
+                ..literal.start + offsets[edit.range.end],
+            text: edit.text,
+        }));
+        for (arg, name) in args.iter().zip(names) {
+            if pun_name(arg).as_ref() != Some(&name) {
+                let start = arg.span().byte_range().start;
+                edits.push(Edit {
+                    range: start..start,
+                    text: format!("{} = ", name),
+                });
+            }
+        }
+
+        Ok(edits)
+    }
+}
+
+impl<'ast> Visit<'ast> for Converter<'_> {
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        let segments = &mac.path.segments;
+        if segments.len() != 2
+            || segments[0].ident != "sqlx"
+            || !segments[1].ident.to_string().starts_with("query")
+        {
+            return;
+        }
+
+        let macro_name = segments[1].ident.to_string();
+        let line = segments[0].ident.span().start().line;
+        let skipped = match self.convert(mac, &macro_name) {
+            Ok(edits) => {
+                self.edits.extend(edits);
+                None
+            }
+            Err(err) => Some(err),
+        };
+        self.notes.push(Note {
+            line,
+            macro_name,
+            skipped,
+        });
+    }
+}
+
+/// The source with its `sqlx` query macros converted, and what happened to each of them
+fn convert_file(src: &str, config: &Config) -> Result<(String, Vec<Note>), String> {
+    let file = syn::parse_file(src)
+        .map_err(|err| format!("{}: failed to parse: {}", err.span().start().line, err))?;
+
+    let mut converter = Converter {
+        src,
+        config,
+        edits: Vec::new(),
+        notes: Vec::new(),
+    };
+    converter.visit_file(&file);
+
+    let mut out = src.to_owned();
+    converter
+        .edits
+        .sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+    for edit in converter.edits {
+        out.replace_range(edit.range, &edit.text);
+    }
+
+    Ok((out, converter.notes))
+}
+
+/// Rewrites the given files, or the `.rs` files in the given directories, in place
+pub fn run(args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut dry_run = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-n" | "--dry-run" => dry_run = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        return Err("convert needs files or directories to convert".to_owned());
+    }
+
+    let config = Config::load(Path::new("."))?;
+
+    let mut rust_files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            files(&path, "rs", &mut rust_files)?;
+        } else {
+            rust_files.push(path);
+        }
+    }
+
+    let mut ok = true;
+    for path in &rust_files {
+        let src = std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        let (out, notes) = match convert_file(&src, &config) {
+            Ok(converted) => converted,
+            Err(err) => {
+                println!("{}:{}", path.display(), err);
+                ok = false;
+                continue;
+            }
+        };
+
+        for note in notes {
+            match note.skipped {
+                Some(reason) => {
+                    println!(
+                        "{}:{}: skipped sqlx::{}!: {}",
+                        path.display(),
+                        note.line,
+                        note.macro_name,
+                        reason
+                    );
+                    ok = false;
+                }
+                None => println!(
+                    "{}:{}: converted sqlx::{}!",
+                    path.display(),
+                    note.line,
+                    note.macro_name
+                ),
+            }
+        }
+        if !dry_run && out != src {
+            std::fs::write(path, out)
+                .map_err(|err| format!("failed to write {}: {}", path.display(), err))?;
+        }
+    }
+
+    Ok(ok)
+}
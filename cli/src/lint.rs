@@ -0,0 +1,223 @@
+//! `sqlx-named lint`
+
+use std::path::{Path, PathBuf};
+
+use sqlparser::tokenizer::Token;
+use sqlx_named_core::{
+    config::{Config, UnusedArgs},
+    header::parse_header,
+    sql, suggest,
+};
+
+use crate::{files, rust::find_calls};
+
+/// The args a query file expects
+struct Query {
+    /// The named placeholders, or `None` for a positional query
+    names: Option<Vec<String>>,
+    /// The args with a `-- $name = expr` default in the header
+    defaults: Vec<String>,
+}
+
+fn analyze(sql: &str, config: &Config) -> Result<Query, String> {
+    let header = parse_header(sql)?;
+    let mut tokens = sql::tokenize(sql, &*config.dialect.tokenizer_dialect(), config.sigil)
+        .map_err(|err| err.to_string())?;
+    sql::expand_column_overrides(&mut tokens, config.dialect.identifier_quote())?;
+    sql::extract_type_hints(&mut tokens)?;
+
+    let (positional, named) = tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Placeholder(placeholder) => Some(placeholder.as_str()),
+            _ => None,
+        })
+        .partition::<Vec<_>, _>(|placeholder| sql::is_positional(placeholder));
+
+    let names = if named.is_empty() {
+        None
+    } else if !positional.is_empty() {
+        return Err(format!(
+            "mixes positional placeholders ({}) with named ones ({})",
+            positional.join(", "),
+            named.join(", ")
+        ));
+    } else {
+        let tokens = sql::expand_optional_predicates(tokens)?;
+        let parts = sql::parse_template(tokens)?;
+        sql::dimensions(&parts)?;
+        let mut names = Vec::<String>::new();
+        for name in sql::referenced_names(&parts) {
+            if !names.iter().any(|known| known == name) {
+                names.push(name.to_owned());
+            }
+        }
+        Some(names)
+    };
+
+    Ok(Query {
+        names,
+        defaults: header
+            .defaults
+            .into_iter()
+            .map(|(name, _)| name.to_owned())
+            .collect(),
+    })
+}
+
+struct Options {
+    verbose: bool,
+    sql_dirs: Vec<PathBuf>,
+    rust_dirs: Vec<PathBuf>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self {
+            verbose: false,
+            sql_dirs: Vec::new(),
+            rust_dirs: Vec::new(),
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-v" | "--verbose" => options.verbose = true,
+                "--rust" => options
+                    .rust_dirs
+                    .push(args.next().ok_or("--rust needs a directory")?.into()),
+                _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+                _ => options.sql_dirs.push(arg.into()),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Prints the problems found, returning whether there were none
+pub fn run(args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut options = Options::parse(args)?;
+    let config = Config::load(Path::new("."))?;
+    if options.sql_dirs.is_empty() {
+        options
+            .sql_dirs
+            .push(config.sql_root.clone().unwrap_or_else(|| ".".into()));
+    }
+
+    let mut ok = true;
+
+    let mut sql_files = Vec::new();
+    for dir in &options.sql_dirs {
+        files(dir, "sql", &mut sql_files)?;
+    }
+    for path in &sql_files {
+        let sql = std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        match analyze(&sql, &config) {
+            Ok(query) if options.verbose => match query.names {
+                Some(names) => println!("{}: ${}", path.display(), names.join(", $")),
+                None => println!("{}: positional", path.display()),
+            },
+            Ok(_) => {}
+            Err(err) => {
+                println!("{}: {}", path.display(), err);
+                ok = false;
+            }
+        }
+    }
+
+    let mut rust_files = Vec::new();
+    for dir in &options.rust_dirs {
+        files(dir, "rs", &mut rust_files)?;
+    }
+    for path in &rust_files {
+        let src = std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        let (calls, errors) = match find_calls(&src) {
+            Ok(found) => found,
+            Err(err) => (Vec::new(), vec![err]),
+        };
+        for err in errors {
+            println!("{}:{}", path.display(), err);
+            ok = false;
+        }
+
+        for call in calls {
+            let location = format!(
+                "{}:{}: {}!(\"{}\")",
+                path.display(),
+                call.line,
+                call.macro_name,
+                call.path
+            );
+            let mut report = |message: String| {
+                println!("{}: {}", location, message);
+                ok = false;
+            };
+
+            let file = config.query_path(Path::new("."), Path::new(&call.path));
+            let query = match std::fs::read_to_string(file) {
+                Ok(sql) => analyze(&sql, &config),
+                Err(err) => Err(format!("failed to read query file: {}", err)),
+            };
+            let query = match query {
+                Ok(query) => query,
+                Err(err) => {
+                    report(err);
+                    continue;
+                }
+            };
+
+            let Some(names) = query.names else {
+                if call.assigned || call.splat {
+                    report("named args given to a positional query".to_owned());
+                }
+                continue;
+            };
+
+            if call.names.iter().any(Option::is_none) {
+                report("arg without a name given to a named query".to_owned());
+            }
+
+            let given = call.names.iter().flatten().collect::<Vec<_>>();
+            let mut unused = given
+                .iter()
+                .map(|name| name.as_str())
+                .filter(|name| !names.iter().any(|known| known == name))
+                .collect::<Vec<_>>();
+            if !call.splat {
+                for name in &names {
+                    if given.contains(&name) || query.defaults.contains(name) {
+                        continue;
+                    }
+                    match suggest::closest(name, unused.iter().copied()) {
+                        Some(suggestion) => {
+                            unused.retain(|name| *name != suggestion);
+                            report(format!(
+                                "arg not given: `{}`, did you mean `{}`?",
+                                name, suggestion
+                            ));
+                        }
+                        None => report(format!("arg not given: `{}`", name)),
+                    }
+                }
+            }
+            // warnings are printed without failing the lint, like the macros only warn
+            match call.unused_args.unwrap_or(config.unused_args) {
+                UnusedArgs::Deny => {
+                    for name in unused {
+                        report(format!("unused arg: `{}`", name));
+                    }
+                }
+                UnusedArgs::Warn => {
+                    for name in unused {
+                        println!("{}: warning: unused arg: `{}`", location, name);
+                    }
+                }
+                UnusedArgs::Allow => {}
+            }
+        }
+    }
+
+    Ok(ok)
+}
@@ -0,0 +1,67 @@
+//! The `sqlx-named` command, run from the root of the crate using the macros
+
+mod convert;
+mod lint;
+mod rust;
+
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+const USAGE: &str = "\
+usage: sqlx-named lint [-v] [--rust <dir>]... [<dir>]...
+       sqlx-named convert [-n] <file or dir>...
+
+commands:
+  lint       checks the .sql files in the given directories, the configured sql_root by default,
+             and with --rust the query_file macro calls in the given source directories
+  convert    rewrites sqlx query macros with positional placeholders into sqlx_named ones, in
+             place or only listing them with -n";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("lint") => lint::run(args),
+        Some("convert") => convert::run(args),
+        Some("-h" | "--help") => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        _ => Err(USAGE.to_owned()),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// All files with the given extension under `dir`, skipping hidden directories and `target`
+fn files(dir: &Path, extension: &str, out: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|err| format!("failed to read {}: {}", dir.display(), err))?;
+
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to read {}: {}", dir.display(), err))?;
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if !name.starts_with('.') && name != "target" {
+                files(&path, extension, out)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == extension) {
+            out.push(path);
+        }
+    }
+
+    Ok(())
+}
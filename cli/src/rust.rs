@@ -0,0 +1,138 @@
+//! Finding `query_file*!` calls in Rust sources
+
+use proc_macro2::TokenTree;
+use sqlx_named_core::{
+    args::{get_name, pun_name},
+    config::UnusedArgs,
+};
+use syn::{
+    parse::{ParseStream, Parser as _},
+    visit::Visit,
+    Attribute, Expr, Ident, LitStr, Macro, Token, Type,
+};
+
+/// A `sqlx_named::query_file*!` invocation
+pub struct Call {
+    pub line: usize,
+    pub macro_name: String,
+    /// The query file, as written
+    pub path: String,
+    /// The arg names, with `None` for args a name can't be inferred for
+    pub names: Vec<Option<String>>,
+    /// Whether some args are given as `name = value`
+    pub assigned: bool,
+    /// Whether a splat gives args that can only be known from its types
+    pub splat: bool,
+    /// The level set by `#[allow/warn/deny(unused_args)]` on the call
+    pub unused_args: Option<UnusedArgs>,
+}
+
+fn parse_call(input: ParseStream, macro_name: String, line: usize) -> syn::Result<Call> {
+    let mut unused_args = None;
+    for attr in input.call(Attribute::parse_outer)? {
+        let level = if attr.path().is_ident("allow") {
+            UnusedArgs::Allow
+        } else if attr.path().is_ident("warn") {
+            UnusedArgs::Warn
+        } else if attr.path().is_ident("deny") {
+            UnusedArgs::Deny
+        } else {
+            continue;
+        };
+        if attr
+            .parse_args::<Ident>()
+            .is_ok_and(|ident| ident == "unused_args")
+        {
+            unused_args = Some(level);
+        }
+    }
+    if macro_name.starts_with("query_file_as") {
+        input.parse::<Type>()?;
+        input.parse::<Token![,]>()?;
+    }
+    let path = input.parse::<LitStr>()?.value();
+
+    let mut names = Vec::new();
+    let mut assigned = false;
+    let mut splat = false;
+    while !input.is_empty() {
+        input.parse::<Token![,]>()?;
+        if input.is_empty() {
+            break;
+        }
+
+        if input.peek(Token![..]) {
+            splat = true;
+            while !input.is_empty() && !input.peek(Token![,]) {
+                input.parse::<TokenTree>()?;
+            }
+        } else if input.peek(Ident) && input.peek2(Token![?]) && input.peek3(Token![=]) {
+            names.push(Some(input.parse::<Ident>()?.to_string()));
+            assigned = true;
+            input.parse::<Token![?]>()?;
+            input.parse::<Token![=]>()?;
+            input.parse::<Expr>()?;
+        } else {
+            names.push(match input.parse::<Expr>()? {
+                Expr::Assign(assign) => {
+                    assigned = true;
+                    get_name(&assign.left)
+                }
+                expr => pun_name(&expr),
+            });
+        }
+    }
+
+    Ok(Call {
+        line,
+        macro_name,
+        path,
+        names,
+        assigned,
+        splat,
+        unused_args,
+    })
+}
+
+struct Finder {
+    calls: Vec<Call>,
+    errors: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for Finder {
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        let segments = &mac.path.segments;
+        let Some(last) = segments.last() else {
+            return;
+        };
+        let macro_name = last.ident.to_string();
+        // `sqlx::query_file!` takes positional args only
+        if !macro_name.starts_with("query_file")
+            || segments.iter().any(|segment| segment.ident == "sqlx")
+        {
+            return;
+        }
+
+        let line = last.ident.span().start().line;
+        let parser = |input: ParseStream| parse_call(input, macro_name.clone(), line);
+        match parser.parse2(mac.tokens.clone()) {
+            Ok(call) => self.calls.push(call),
+            Err(err) => self
+                .errors
+                .push(format!("{}: {}!: {}", line, macro_name, err)),
+        }
+    }
+}
+
+/// The calls in a source file, and the ones that couldn't be parsed as `line: error`
+pub fn find_calls(src: &str) -> Result<(Vec<Call>, Vec<String>), String> {
+    let file = syn::parse_file(src)
+        .map_err(|err| format!("{}: failed to parse: {}", err.span().start().line, err))?;
+
+    let mut finder = Finder {
+        calls: Vec::new(),
+        errors: Vec::new(),
+    };
+    finder.visit_file(&file);
+    Ok((finder.calls, finder.errors))
+}
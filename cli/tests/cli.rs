@@ -0,0 +1,114 @@
+use std::process::Command;
+
+#[test]
+fn test_lint() {
+    let out = Command::new(env!("CARGO_BIN_EXE_sqlx-named"))
+        .args(["lint", "--rust", "src", "queries"])
+        .current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+        .output()
+        .unwrap();
+
+    assert!(!out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "\
+queries/mixed.sql: mixes positional placeholders ($1) with named ones ($name)
+src/lib.rs:2: query_file!(\"queries/user.sql\"): arg not given: `usr_id`, did you mean `user_id`?
+src/lib.rs:6: query_file!(\"queries/positional.sql\"): named args given to a positional query
+src/lib.rs:7: query_file!(\"queries/user.sql\"): unused arg: `page`
+src/lib.rs:8: query_file!(\"queries/user.sql\"): warning: unused arg: `page`
+"
+    );
+}
+
+#[test]
+fn test_lint_config() {
+    let dir = format!("{}/lint-config", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::create_dir_all(format!("{}/src", dir)).unwrap();
+    std::fs::write(
+        format!("{}/sqlx-named.toml", dir),
+        "sql_root = \"queries\"\nunused_args = \"warn\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(format!("{}/queries", dir)).unwrap();
+    std::fs::write(
+        format!("{}/queries/user.sql", dir),
+        "select * from users where id = $id",
+    )
+    .unwrap();
+    std::fs::write(
+        format!("{}/src/lib.rs", dir),
+        r#"fn queries(id: i32) {
+    sqlx_named::query_file!("user.sql", id, page = 1);
+    sqlx_named::query_file!(#[deny(unused_args)] "user.sql", id, page = 1);
+}
+"#,
+    )
+    .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_sqlx-named"))
+        .args(["lint", "--rust", "src"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    assert!(!out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "\
+src/lib.rs:2: query_file!(\"user.sql\"): warning: unused arg: `page`
+src/lib.rs:3: query_file!(\"user.sql\"): unused arg: `page`
+"
+    );
+}
+
+#[test]
+fn test_convert() {
+    let dir = env!("CARGO_TARGET_TMPDIR");
+    let path = format!("{}/convert.rs", dir);
+    std::fs::copy(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/convert.rs"),
+        &path,
+    )
+    .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_sqlx-named"))
+        .args(["convert", "convert.rs"])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+
+    assert!(!out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "\
+convert.rs:2: converted sqlx::query!
+convert.rs:3: converted sqlx::query_as!
+convert.rs:9: converted sqlx::query_scalar!
+convert.rs:10: skipped sqlx::query_file!: query files have to be converted by hand
+convert.rs:11: converted sqlx::query!
+convert.rs:16: converted sqlx::query!
+"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        r##"async fn queries(db: &sqlx::PgPool, user: &User, id: i32) {
+    sqlx_named::query!("select * from users where id = $id", id);
+    sqlx_named::query_as!(
+        User,
+        r#"select id, name as "name!" from users where id = $id or name = $arg2 or id = $id"#,
+        id = &id,
+        arg2 = user.name,
+    );
+    sqlx_named::query_scalar!("select 1");
+    sqlx::query_file!("queries/user.sql", id);
+    sqlx_named::query!(
+        "select E'a\\'b', 'caf\u{e9}' /* $2 */ \
+         where name = $arg1",
+        arg1 = user.name,
+    );
+    sqlx_named::query!("select $arg2, $arg1, $id", arg2 = user.id, arg1, id as i64);
+}
+"##
+    );
+}
@@ -0,0 +1,17 @@
+async fn queries(db: &sqlx::PgPool, user: &User, id: i32) {
+    sqlx::query!("select * from users where id = $1", id);
+    sqlx::query_as!(
+        User,
+        r#"select id, name as "name!" from users where id = $1 or name = $2 or id = $1"#,
+        &id,
+        user.name,
+    );
+    sqlx::query_scalar!("select 1");
+    sqlx::query_file!("queries/user.sql", id);
+    sqlx::query!(
+        "select E'a\\'b', 'caf\u{e9}' /* $2 */ \
+         where name = $1",
+        user.name,
+    );
+    sqlx::query!("select $1, $2, $3", user.id, arg1, id as i64);
+}
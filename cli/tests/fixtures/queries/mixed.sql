@@ -0,0 +1 @@
+select * from users where id = $1 or name = $name
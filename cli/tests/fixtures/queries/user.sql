@@ -0,0 +1,2 @@
+-- $limit = 10
+select * from users where id = $usr_id limit $limit
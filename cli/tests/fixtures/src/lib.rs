@@ -0,0 +1,10 @@
+async fn queries(db: &sqlx::PgPool, user_id: i32, args: Args) {
+    sqlx_named::query_file!("queries/user.sql", user_id);
+    sqlx_named::query_file!("queries/user.sql", usr_id = user_id, limit = 1);
+    sqlx_named::query_file!("queries/user.sql", ..args);
+    sqlx_named::query_file!("queries/positional.sql", user_id);
+    sqlx_named::query_file!("queries/positional.sql", id = user_id);
+    sqlx_named::query_file!("queries/user.sql", usr_id = user_id, page = 1);
+    sqlx_named::query_file!(#[warn(unused_args)] "queries/user.sql", usr_id = user_id, page = 1);
+    sqlx_named::query_file!(#[allow(unused_args)] "queries/user.sql", usr_id = user_id, page = 1);
+}
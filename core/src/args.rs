@@ -0,0 +1,53 @@
+//! Inferring arg names from Rust expressions
+
+use syn::{Expr, UnOp};
+
+/// The identifier an expression refers to, looking through `&`, `*`, `.await` and `?`
+pub fn get_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) => Some(path.path.get_ident()?.to_string()),
+        Expr::Reference(rf) => get_name(&rf.expr),
+        Expr::Await(wait) => get_name(&wait.base),
+        Expr::Try(tr) => get_name(&tr.expr),
+        Expr::Unary(un) if matches!(&un.op, UnOp::Deref(_)) => get_name(&un.expr),
+        _ => None,
+    }
+}
+
+/// The name an arg given without one is used as: a plain identifier, or anything [`get_name`]
+/// accepts with a type override
+pub fn pun_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(_) => get_name(expr),
+        Expr::Cast(cast) => get_name(&cast.expr),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(src: &str) -> Expr {
+        syn::parse_str(src).unwrap()
+    }
+
+    #[test]
+    fn test_get_name() {
+        assert_eq!(get_name(&parse("id")).as_deref(), Some("id"));
+        assert_eq!(get_name(&parse("&mut *id")).as_deref(), Some("id"));
+        assert_eq!(get_name(&parse("id.await?")).as_deref(), Some("id"));
+        assert_eq!(get_name(&parse("user.id")), None);
+        assert_eq!(get_name(&parse("crate::ID")), None);
+        assert_eq!(get_name(&parse("id()")), None);
+    }
+
+    #[test]
+    fn test_pun_name() {
+        assert_eq!(pun_name(&parse("id")).as_deref(), Some("id"));
+        assert_eq!(pun_name(&parse("id as i64")).as_deref(), Some("id"));
+        assert_eq!(pun_name(&parse("&id as &str")).as_deref(), Some("id"));
+        assert_eq!(pun_name(&parse("&id")), None);
+        assert_eq!(pun_name(&parse("user.id as i64")), None);
+    }
+}
@@ -0,0 +1,226 @@
+//! Per-crate settings from an optional `sqlx-named.toml` next to `Cargo.toml`
+
+use std::path::{Path, PathBuf};
+
+use serde::{de::Error as _, Deserialize, Deserializer};
+use sqlparser::dialect::{GenericDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
+
+pub const FILE_NAME: &str = "sqlx-named.toml";
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    #[default]
+    #[serde(alias = "postgresql")]
+    Postgres,
+    MySql,
+    Sqlite,
+    Generic,
+}
+
+impl Dialect {
+    pub fn tokenizer_dialect(self) -> Box<dyn sqlparser::dialect::Dialect> {
+        match self {
+            Dialect::Postgres => Box::new(PostgreSqlDialect {}),
+            Dialect::MySql => Box::new(MySqlDialect {}),
+            Dialect::Sqlite => Box::new(SQLiteDialect {}),
+            Dialect::Generic => Box::new(GenericDialect {}),
+        }
+    }
+
+    /// The character quoting identifiers
+    pub fn identifier_quote(self) -> char {
+        match self {
+            Dialect::MySql => '`',
+            Dialect::Postgres | Dialect::Sqlite | Dialect::Generic => '"',
+        }
+    }
+
+    /// The positional placeholder for the arg at `position`, or `None` when every occurrence is
+    /// a separate `?`
+    pub fn placeholder(self, position: usize) -> Option<String> {
+        match self {
+            Dialect::Postgres | Dialect::Generic => Some(format!("${}", position)),
+            Dialect::Sqlite => Some(format!("?{}", position)),
+            Dialect::MySql => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnusedArgs {
+    #[default]
+    Deny,
+    Warn,
+    Allow,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub dialect: Dialect,
+    /// The character starting named placeholders
+    #[serde(deserialize_with = "sigil")]
+    pub sigil: char,
+    /// The directory query file paths are relative to, instead of the manifest's
+    pub sql_root: Option<PathBuf>,
+    pub unused_args: UnusedArgs,
+    /// Binds every occurrence of a named arg separately, like `#[bind_each]` on every query
+    pub bind_each: bool,
+    /// Overrides the `sql-comments` feature and `SQLX_NAMED_SQL_COMMENTS`
+    pub comments: Option<bool>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            dialect: Dialect::default(),
+            sigil: '$',
+            sql_root: None,
+            unused_args: UnusedArgs::default(),
+            bind_each: false,
+            comments: None,
+        }
+    }
+}
+
+fn sigil<'de, D: Deserializer<'de>>(deserializer: D) -> Result<char, D::Error> {
+    match char::deserialize(deserializer)? {
+        sigil @ ('$' | ':' | '@') => Ok(sigil),
+        other => Err(D::Error::custom(format!(
+            "unsupported sigil `{}`, expected one of: $, :, @",
+            other
+        ))),
+    }
+}
+
+impl Config {
+    /// Reads the config of the crate in `dir`, falling back to the defaults without a file
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let Ok(src) = std::fs::read_to_string(dir.join(FILE_NAME)) else {
+            return Ok(Self::default());
+        };
+        toml::from_str(&src).map_err(|err| format!("{}: {}", FILE_NAME, err))
+    }
+
+    /// Where the query file at `path` is, for the crate in `dir`
+    pub fn query_path(&self, dir: &Path, path: &Path) -> PathBuf {
+        match &self.sql_root {
+            Some(sql_root) => dir.join(sql_root).join(path),
+            None => dir.join(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(src: &str) -> Result<Config, String> {
+        toml::from_str(src).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = parse("").unwrap();
+        assert_eq!(config.dialect, Dialect::Postgres);
+        assert_eq!(config.sigil, '$');
+        assert_eq!(config.sql_root, None);
+        assert_eq!(config.unused_args, UnusedArgs::Deny);
+        assert!(!config.bind_each);
+        assert_eq!(config.comments, None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = parse(
+            r#"
+            # every key
+            dialect = "mysql"
+            sigil = ":"
+            sql_root = "sql"
+            unused_args = "warn"
+            bind_each = true
+            comments = false
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.dialect, Dialect::MySql);
+        assert_eq!(config.sigil, ':');
+        assert_eq!(config.sql_root, Some(PathBuf::from("sql")));
+        assert_eq!(config.unused_args, UnusedArgs::Warn);
+        assert!(config.bind_each);
+        assert_eq!(config.comments, Some(false));
+
+        assert_eq!(
+            parse(r#"dialect = "postgresql""#).unwrap().dialect,
+            Dialect::Postgres
+        );
+        assert_eq!(
+            parse(r#"dialect = "sqlite""#).unwrap().dialect,
+            Dialect::Sqlite
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(r#"dialect = "oracle""#)
+            .unwrap_err()
+            .contains("unknown variant `oracle`"));
+        assert!(parse(r##"sigil = "#""##)
+            .unwrap_err()
+            .contains("unsupported sigil `#`, expected one of: $, :, @"));
+        assert!(parse("bind_each = \"yes\"")
+            .unwrap_err()
+            .contains("invalid type"));
+        assert!(parse("dialects = \"mysql\"")
+            .unwrap_err()
+            .contains("unknown field `dialects`"));
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = std::env::temp_dir().join(format!("sqlx-named-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let config = Config::load(&dir).unwrap();
+        assert_eq!(config.dialect, Dialect::Postgres);
+
+        std::fs::write(
+            dir.join(FILE_NAME),
+            "dialect = \"sqlite\"\nsql_root = \"queries\"\n",
+        )
+        .unwrap();
+        let config = Config::load(&dir).unwrap();
+        assert_eq!(config.dialect, Dialect::Sqlite);
+        assert_eq!(
+            config.query_path(&dir, Path::new("users/get.sql")),
+            dir.join("queries/users/get.sql")
+        );
+
+        std::fs::write(dir.join(FILE_NAME), "sigil = 1").unwrap();
+        let err = Config::load(&dir).unwrap_err();
+        assert!(err.starts_with("sqlx-named.toml: "));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_query_path() {
+        let config = Config::default();
+        assert_eq!(
+            config.query_path(Path::new("/crate"), Path::new("sql/get.sql")),
+            Path::new("/crate/sql/get.sql")
+        );
+    }
+
+    #[test]
+    fn test_dialects() {
+        assert_eq!(Dialect::Postgres.placeholder(2).as_deref(), Some("$2"));
+        assert_eq!(Dialect::Sqlite.placeholder(2).as_deref(), Some("?2"));
+        assert_eq!(Dialect::MySql.placeholder(2), None);
+        assert_eq!(Dialect::MySql.identifier_quote(), '`');
+        assert_eq!(Dialect::Postgres.identifier_quote(), '"');
+    }
+}
@@ -0,0 +1,53 @@
+/// Declarations in the leading `--` comments of a query file, as written
+#[derive(Default)]
+pub struct Header<'a> {
+    /// `-- $name = expr`
+    pub defaults: Vec<(&'a str, &'a str)>,
+    /// `-- param: name Type`
+    pub param_types: Vec<(&'a str, &'a str)>,
+}
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn parse_header(sql: &str) -> Result<Header<'_>, String> {
+    let mut header = Header::default();
+
+    for line in sql.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let Some(comment) = line.strip_prefix("--") else {
+            break;
+        };
+        let comment = comment.trim();
+
+        if let Some(param) = comment.strip_prefix("param:") {
+            let param = param.trim();
+            let (name, typ) = param.split_once(char::is_whitespace).unwrap_or((param, ""));
+            if !is_ident(name) {
+                return Err(format!(
+                    "invalid param name `{}` in query file header",
+                    name
+                ));
+            }
+            header.param_types.push((name, typ.trim()));
+        } else if let Some(default) = comment.strip_prefix('$') {
+            let Some((name, expr)) = default.split_once('=') else {
+                continue;
+            };
+            let name = name.trim();
+            if !is_ident(name) {
+                continue;
+            }
+            header.defaults.push((name, expr.trim()));
+        }
+    }
+
+    Ok(header)
+}
@@ -0,0 +1,8 @@
+//! The query rewriting behind `sqlx-named`, independent of the proc macro so the CLI can use it
+
+pub mod args;
+pub mod config;
+pub mod header;
+pub mod meta;
+pub mod sql;
+pub mod suggest;
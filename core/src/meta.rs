@@ -0,0 +1,23 @@
+//! The description of a query invocation produced by `sqlx_named::meta!`
+
+/// A query macro invocation, described without running it
+#[derive(Debug, Clone, Copy)]
+pub struct QueryMeta {
+    /// The name of the wrapped macro, e.g. `query_as`
+    pub macro_name: &'static str,
+    /// The sql as written at the call site or in the query file
+    pub original_sql: &'static str,
+    /// Every combination of conditional blocks and choices
+    pub variants: &'static [QueryVariantMeta],
+    pub file: &'static str,
+    pub line: u32,
+}
+
+/// One of the queries an invocation can run
+#[derive(Debug, Clone, Copy)]
+pub struct QueryVariantMeta {
+    /// The sql passed on to `sqlx`
+    pub sql: &'static str,
+    /// The name of each arg with the position it's bound to
+    pub params: &'static [(&'static str, usize)],
+}
@@ -0,0 +1,842 @@
+use std::{iter::Peekable, ops::Range};
+
+use sqlparser::{
+    dialect::Dialect,
+    keywords::Keyword,
+    tokenizer::{Token, TokenWithLocation, Tokenizer, TokenizerError, Whitespace, Word},
+};
+
+/// A token the query rewriting works on: a plain [`Token`], or one along with the byte range of
+/// the query it comes from, which [`render_located`] copies instead of rendering the token
+pub trait SqlToken: Clone {
+    fn token(&self) -> &Token;
+    /// A token that isn't part of the original query
+    fn new(token: Token) -> Self;
+}
+
+impl SqlToken for Token {
+    fn token(&self) -> &Token {
+        self
+    }
+
+    fn new(token: Token) -> Self {
+        token
+    }
+}
+
+impl SqlToken for (Token, Option<Range<usize>>) {
+    fn token(&self) -> &Token {
+        &self.0
+    }
+
+    fn new(token: Token) -> Self {
+        (token, None)
+    }
+}
+
+/// The query the tokens make up, keeping the source of the ones that come from `sql`
+pub fn render_located(sql: &str, tokens: &[(Token, Option<Range<usize>>)]) -> String {
+    tokens
+        .iter()
+        .map(|(token, range)| match range {
+            Some(range) => sql[range.clone()].to_owned(),
+            None => token.to_string(),
+        })
+        .collect()
+}
+
+/// The byte range of each token in `sql`, each one ending where the next starts and the last
+/// at `end`
+fn token_ranges(sql: &str, located: &[TokenWithLocation], end: usize) -> Vec<Range<usize>> {
+    let mut chars = sql.char_indices().peekable();
+    let (mut line, mut column) = (1, 1);
+
+    let mut starts = Vec::with_capacity(located.len());
+    for token in located {
+        while (line, column) < (token.location.line, token.location.column) {
+            match chars.next() {
+                Some((_, '\n')) => (line, column) = (line + 1, 1),
+                Some(_) => column += 1,
+                None => break,
+            }
+        }
+        starts.push(chars.peek().map_or(sql.len(), |&(offset, _)| offset));
+    }
+
+    let ends = starts.iter().skip(1).copied().chain([end]);
+    starts
+        .iter()
+        .zip(ends)
+        .map(|(&start, end)| start..end)
+        .collect()
+}
+
+pub fn tokenize(
+    sql: &str,
+    dialect: &dyn Dialect,
+    sigil: char,
+) -> Result<Vec<Token>, TokenizerError> {
+    let tokens = tokenize_located(sql, dialect, sigil)?;
+    Ok(tokens.into_iter().map(|(token, _)| token).collect())
+}
+
+/// [`tokenize`], along with the byte range of `sql` each token comes from
+pub fn tokenize_located(
+    sql: &str,
+    dialect: &dyn Dialect,
+    sigil: char,
+) -> Result<Vec<(Token, Range<usize>)>, TokenizerError> {
+    let mut tokens = Vec::new();
+    let mut base = 0;
+
+    // sqlparser's postgres tokenizer swallows the character following a lone `?`,
+    // so tokenizing is restarted right after each one
+    loop {
+        let rest = &sql[base..];
+        let mut located = match Tokenizer::new(dialect, rest).tokenize_with_location() {
+            Ok(located) => located,
+            // the swallowed character can be a quote, making the rest look unterminated
+            Err(err) => tokenize_to_question(rest, dialect).ok_or(err)?,
+        };
+
+        let Some(pos) = located
+            .iter()
+            .position(|token| token.token == Token::Question)
+        else {
+            let ranges = token_ranges(rest, &located, rest.len());
+            tokens.extend(shift(located, ranges, base));
+            return Ok(normalize_placeholders(tokens, sigil));
+        };
+
+        located.truncate(pos + 1);
+        let mut ranges = token_ranges(rest, &located, rest.len());
+        let question = ranges[pos].start;
+        ranges[pos] = question..question + 1;
+        tokens.extend(shift(located, ranges, base));
+        base += question + 1;
+    }
+}
+
+/// The tokens of `sql` up to and including its first lone `?` outside of literals and comments
+fn tokenize_to_question(sql: &str, dialect: &dyn Dialect) -> Option<Vec<TokenWithLocation>> {
+    sql.match_indices('?')
+        .filter(|(pos, _)| !matches!(sql[pos + 1..].chars().next(), Some('|' | '&')))
+        .find_map(|(pos, _)| {
+            let located = Tokenizer::new(dialect, &sql[..=pos])
+                .tokenize_with_location()
+                .ok()?;
+            (located.last()?.token == Token::Question).then_some(located)
+        })
+}
+
+fn shift(
+    located: Vec<TokenWithLocation>,
+    ranges: Vec<Range<usize>>,
+    base: usize,
+) -> impl Iterator<Item = (Token, Range<usize>)> {
+    located
+        .into_iter()
+        .zip(ranges)
+        .map(move |(token, range)| (token.token, range.start + base..range.end + base))
+}
+
+fn is_unquoted(token: Option<&Token>) -> bool {
+    matches!(token, Some(Token::Word(word)) if word.quote_style.is_none())
+}
+
+/// Turns named placeholders into `$name` placeholder tokens, whatever their sigil, since mysql
+/// and sqlite tokenize them as identifiers and `:name` as a colon followed by one
+fn normalize_placeholders(
+    tokens: Vec<(Token, Range<usize>)>,
+    sigil: char,
+) -> Vec<(Token, Range<usize>)> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some((token, range)) = tokens.next() {
+        let token = match token {
+            Token::Word(word)
+                if word.quote_style.is_none()
+                    && (word.value.starts_with('$') || word.value.starts_with(sigil)) =>
+            {
+                (Token::Placeholder(format!("${}", &word.value[1..])), range)
+            }
+            Token::Placeholder(placeholder) if sigil != '$' && placeholder.starts_with(sigil) => {
+                (Token::Placeholder(format!("${}", &placeholder[1..])), range)
+            }
+            Token::Colon | Token::AtSign
+                if token.to_string().starts_with(sigil)
+                    && is_unquoted(tokens.peek().map(|(token, _)| token)) =>
+            {
+                let Some((Token::Word(word), word_range)) = tokens.next() else {
+                    unreachable!()
+                };
+                (
+                    Token::Placeholder(format!("${}", word.value)),
+                    range.start..word_range.end,
+                )
+            }
+            token => (token, range),
+        };
+        out.push(token);
+    }
+
+    out
+}
+
+/// Whether a placeholder refers to an arg by position
+pub fn is_positional(placeholder: &str) -> bool {
+    placeholder == "?"
+        || (placeholder.len() > 1 && placeholder.chars().skip(1).all(|c| c.is_ascii_digit()))
+}
+
+// `$name?` with postgres, where `?` is an operator, and `?` is a placeholder everywhere else
+fn is_question(token: Option<&Token>) -> bool {
+    match token {
+        Some(Token::Question) => true,
+        Some(Token::Placeholder(placeholder)) => placeholder == "?",
+        _ => false,
+    }
+}
+
+fn is_comparison(token: &Token) -> bool {
+    match token {
+        Token::Eq | Token::Neq | Token::Lt | Token::Gt | Token::LtEq | Token::GtEq => true,
+        Token::Word(word) => matches!(word.keyword, Keyword::LIKE | Keyword::ILIKE),
+        _ => false,
+    }
+}
+
+fn pop_whitespace<T: SqlToken>(tokens: &mut Vec<T>) -> Vec<T> {
+    let start = tokens
+        .iter()
+        .rposition(|token| !matches!(token.token(), Token::Whitespace(_)))
+        .map_or(0, |pos| pos + 1);
+    tokens.split_off(start)
+}
+
+// splits `column = ` off the end of `tokens`
+fn pop_comparison<T: SqlToken>(tokens: &mut Vec<T>) -> Option<Vec<T>> {
+    let after_op = pop_whitespace(tokens);
+    let op = tokens.pop().filter(|op| is_comparison(op.token()))?;
+    let before_op = pop_whitespace(tokens);
+
+    let start = tokens
+        .iter()
+        .rposition(|token| {
+            !matches!(
+                token.token(),
+                Token::Word(_) | Token::Period | Token::DoubleColon
+            )
+        })
+        .map_or(0, |pos| pos + 1);
+    let column = tokens.split_off(start);
+    if !matches!(column.first().map(T::token), Some(Token::Word(_))) {
+        return None;
+    }
+
+    Some(
+        column
+            .into_iter()
+            .chain(before_op)
+            .chain([op])
+            .chain(after_op)
+            .collect(),
+    )
+}
+
+fn spaced_keywords<'a, T: SqlToken + 'a>(keywords: &'a [&str]) -> impl Iterator<Item = T> + 'a {
+    keywords.iter().flat_map(|keyword| {
+        [
+            T::new(Token::Whitespace(Whitespace::Space)),
+            T::new(Token::make_keyword(keyword)),
+        ]
+    })
+}
+
+fn parse_type_hint<T: SqlToken>(
+    placeholder: &str,
+    tokens: &mut Peekable<impl Iterator<Item = T>>,
+) -> Result<(String, Option<T>), String> {
+    let mut typ = String::new();
+    let mut depth = 0usize;
+    let mut expect_word = true;
+
+    loop {
+        if depth > 0 {
+            skip_whitespace(tokens);
+        }
+        if expect_word {
+            match tokens.next().as_ref().map(T::token) {
+                Some(Token::Word(word)) => typ.push_str(&word.to_string()),
+                _ => return Err(format!("expected a type after `{placeholder}:`")),
+            }
+            expect_word = false;
+            continue;
+        }
+        match tokens.peek().map(T::token) {
+            Some(Token::DoubleColon) => {
+                typ.push_str("::");
+                expect_word = true;
+            }
+            Some(Token::Lt) => {
+                depth += 1;
+                typ.push('<');
+                expect_word = true;
+            }
+            Some(Token::Comma) if depth > 0 => {
+                typ.push_str(", ");
+                expect_word = true;
+            }
+            Some(Token::Gt) if depth > 0 => {
+                depth -= 1;
+                typ.push('>');
+            }
+            Some(Token::ShiftRight) if depth > 1 => {
+                depth -= 2;
+                typ.push_str(">>");
+            }
+            // postgres allows custom operators like `>?`, which swallow the `?` of an optional
+            // predicate following the type
+            Some(Token::CustomBinaryOperator(op))
+                if op.len() == depth + 1
+                    && op.ends_with('?')
+                    && op[..depth].bytes().all(|b| b == b'>') =>
+            {
+                typ.push_str(&op[..depth]);
+                tokens.next();
+                return Ok((typ, Some(T::new(Token::Question))));
+            }
+            _ if depth > 0 => return Err(format!("unclosed `<` in the type of `{placeholder}`")),
+            _ => return Ok((typ, None)),
+        }
+        tokens.next();
+    }
+}
+
+/// Strips `$name: Type` hints out of the query, returning the hinted types by placeholder name.
+///
+/// The colon has to directly follow the placeholder and be followed by an identifier, and colons
+/// within brackets are never hints, so that array slices like `arr[$lower:upper]` are left alone
+pub fn extract_type_hints<T: SqlToken>(
+    tokens: &mut Vec<T>,
+) -> Result<Vec<(String, String)>, String> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut hints: Vec<(String, String)> = Vec::new();
+    let mut input = std::mem::take(tokens).into_iter().peekable();
+    let mut brackets = 0usize;
+
+    while let Some(token) = input.next() {
+        let Token::Placeholder(placeholder) = token.token().clone() else {
+            match token.token() {
+                Token::LBracket => brackets += 1,
+                Token::RBracket => brackets = brackets.saturating_sub(1),
+                _ => {}
+            }
+            out.push(token);
+            continue;
+        };
+        out.push(token);
+        if brackets > 0 || input.peek().map(T::token) != Some(&Token::Colon) {
+            continue;
+        }
+
+        let colon = input.next().unwrap();
+        let mut whitespace = Vec::new();
+        while let Some(Token::Whitespace(_)) = input.peek().map(T::token) {
+            whitespace.extend(input.next());
+        }
+        if !matches!(input.peek().map(T::token), Some(Token::Word(_))) {
+            out.push(colon);
+            out.extend(whitespace);
+            continue;
+        }
+
+        let (typ, rest) = parse_type_hint(&placeholder, &mut input)?;
+        out.extend(rest);
+
+        let name = &placeholder[1..];
+        match hints.iter().find(|(hinted, _)| hinted == name) {
+            Some((_, hinted_typ)) if *hinted_typ != typ => {
+                return Err(format!(
+                    "conflicting type hints for `{placeholder}`: `{hinted_typ}` and `{typ}`"
+                ))
+            }
+            Some(_) => {}
+            None => hints.push((name.to_owned(), typ)),
+        }
+    }
+
+    *tokens = out;
+    Ok(hints)
+}
+
+/// Rewrites `$col:name!`, `$col:name?` and `$col:name: Type` column overrides, which can also be
+/// combined as in `$col:name?: Type`, into the quoted `"name!"`, `"name?"` and `"name: Type"`
+/// aliases `sqlx` reads nullability and types from, returning whether there were any
+pub fn expand_column_overrides<T: SqlToken>(
+    tokens: &mut Vec<T>,
+    quote: char,
+) -> Result<bool, String> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut input = std::mem::take(tokens).into_iter().peekable();
+    let mut expanded = false;
+
+    while let Some(token) = input.next() {
+        if !matches!(token.token(), Token::Placeholder(placeholder) if placeholder == "$col") {
+            out.push(token);
+            continue;
+        }
+        let mut alias = match input.peek().map(T::token) {
+            Some(Token::Colon) => {
+                input.next();
+                match input.next().as_ref().map(T::token) {
+                    Some(Token::Word(word)) if word.quote_style.is_none() => word.value.clone(),
+                    _ => return Err("expected a column name after `$col:`".to_owned()),
+                }
+            }
+            // with `:` as the sigil, the name is tokenized as a placeholder
+            Some(Token::Placeholder(name)) if !is_positional(name) => {
+                let name = name[1..].to_owned();
+                input.next();
+                name
+            }
+            _ => {
+                out.push(token);
+                continue;
+            }
+        };
+
+        if input.peek().map(T::token) == Some(&Token::ExclamationMark) {
+            input.next();
+            alias.push('!');
+        } else if is_question(input.peek().map(T::token)) {
+            input.next();
+            alias.push('?');
+        }
+
+        let mut rest = None;
+        if input.peek().map(T::token) == Some(&Token::Colon) {
+            input.next();
+            skip_whitespace(&mut input);
+            let (typ, after) = parse_type_hint(&format!("$col:{}", alias), &mut input)?;
+            alias = format!("{}: {}", alias, typ);
+            rest = after;
+        }
+
+        out.push(T::new(Token::Word(Word {
+            value: alias,
+            quote_style: Some(quote),
+            keyword: Keyword::NoKeyword,
+        })));
+        out.extend(rest);
+        expanded = true;
+    }
+
+    *tokens = out;
+    Ok(expanded)
+}
+
+/// Rewrites `column = $name?` into `(column = $name or $name is null)`
+pub fn expand_optional_predicates<T: SqlToken>(tokens: Vec<T>) -> Result<Vec<T>, String> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        let Token::Placeholder(placeholder) = token.token().clone() else {
+            out.push(token);
+            continue;
+        };
+        if !is_question(tokens.peek().map(T::token)) {
+            out.push(token);
+            continue;
+        }
+        tokens.next();
+
+        let mut param = vec![token];
+        while tokens.peek().map(T::token) == Some(&Token::DoubleColon) {
+            param.extend(tokens.next());
+            match tokens.next() {
+                Some(typ) if matches!(typ.token(), Token::Word(_)) => param.push(typ),
+                _ => return Err(format!("expected a type after `{placeholder}?::`")),
+            }
+        }
+
+        let comparison = pop_comparison(&mut out).ok_or_else(|| {
+            format!("`{placeholder}?` must be the right-hand side of a comparison with a column")
+        })?;
+
+        // the comparison goes first so that postgres infers the parameter's type from it
+        out.push(T::new(Token::LParen));
+        out.extend(comparison);
+        out.extend(param.iter().cloned());
+        out.extend(spaced_keywords(&["or"]));
+        out.push(T::new(Token::Whitespace(Whitespace::Space)));
+        out.extend(param);
+        out.extend(spaced_keywords(&["is", "null"]));
+        out.push(T::new(Token::RParen));
+    }
+
+    Ok(out)
+}
+
+pub enum Part<T = Token> {
+    Token(T),
+    /// `[[ ... ]]`, only included when all of its args are `Some`
+    Block(Vec<Part<T>>),
+    /// `${name in [a, b]}`, replaced with one of the options depending on the arg
+    Choice {
+        name: String,
+        options: Vec<T>,
+    },
+}
+
+fn skip_whitespace<T: SqlToken>(tokens: &mut Peekable<impl Iterator<Item = T>>) {
+    while matches!(tokens.peek().map(T::token), Some(Token::Whitespace(_))) {
+        tokens.next();
+    }
+}
+
+fn parse_choice<T: SqlToken>(
+    tokens: &mut Peekable<impl Iterator<Item = T>>,
+) -> Result<Part<T>, String> {
+    const EXPECTED: &str = "expected `${name in [option, ...]}`";
+
+    skip_whitespace(tokens);
+    let Some(Token::Word(name)) = tokens.next().map(|token| token.token().clone()) else {
+        return Err(EXPECTED.to_owned());
+    };
+    skip_whitespace(tokens);
+    match tokens.next().as_ref().map(T::token) {
+        Some(Token::Word(word)) if word.keyword == Keyword::IN => {}
+        _ => return Err(EXPECTED.to_owned()),
+    }
+    skip_whitespace(tokens);
+    if tokens.next().as_ref().map(T::token) != Some(&Token::LBracket) {
+        return Err(EXPECTED.to_owned());
+    }
+
+    let mut options = Vec::new();
+    loop {
+        skip_whitespace(tokens);
+        match tokens.next() {
+            Some(option) if matches!(option.token(), Token::Word(_)) => options.push(option),
+            _ => {
+                return Err(format!(
+                    "expected an identifier in the options of `{}`",
+                    name.value
+                ))
+            }
+        }
+        skip_whitespace(tokens);
+        match tokens.next().as_ref().map(T::token) {
+            Some(Token::Comma) => {}
+            Some(Token::RBracket) => break,
+            _ => return Err(EXPECTED.to_owned()),
+        }
+    }
+
+    skip_whitespace(tokens);
+    if tokens.next().as_ref().map(T::token) != Some(&Token::RBrace) {
+        return Err(EXPECTED.to_owned());
+    }
+
+    Ok(Part::Choice {
+        name: name.value,
+        options,
+    })
+}
+
+/// Splits `[[ ... ]]` conditional blocks and `${name in [...]}` identifier choices out of the
+/// query.
+///
+/// Only a `[[` at the start of the query or after whitespace opens a block, so that array
+/// constructors and subscripts like `array[[1, 2]]` are left alone
+pub fn parse_template<T: SqlToken>(tokens: Vec<T>) -> Result<Vec<Part<T>>, String> {
+    let mut parts = Vec::new();
+    let mut block: Option<(Vec<Part<T>>, usize)> = None;
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        if *token.token() == Token::Placeholder("$".to_owned())
+            && tokens.peek().map(T::token) == Some(&Token::LBrace)
+        {
+            tokens.next();
+            let choice = parse_choice(&mut tokens)?;
+            match &mut block {
+                Some((block_parts, _)) => block_parts.push(choice),
+                None => parts.push(choice),
+            }
+            continue;
+        }
+
+        let opens = *token.token() == Token::LBracket
+            && tokens.peek().map(T::token) == Some(&Token::LBracket);
+        let after_whitespace = |parts: &[Part<T>]| matches!(parts.last(), Some(Part::Token(token)) if matches!(token.token(), Token::Whitespace(_)));
+
+        match &mut block {
+            None => {
+                if opens && (parts.is_empty() || after_whitespace(&parts)) {
+                    tokens.next();
+                    block = Some((Vec::new(), 0));
+                } else {
+                    parts.push(Part::Token(token));
+                }
+            }
+            Some((block_parts, depth)) => match token.token() {
+                Token::LBracket => {
+                    if opens && *depth == 0 && after_whitespace(block_parts) {
+                        return Err("conditional blocks can't be nested".to_owned());
+                    }
+                    *depth += 1;
+                    block_parts.push(Part::Token(token));
+                }
+                Token::RBracket
+                    if *depth == 0 && tokens.peek().map(T::token) == Some(&Token::RBracket) =>
+                {
+                    tokens.next();
+                    let (block_parts, _) = block.take().unwrap();
+                    parts.push(Part::Block(block_parts));
+                }
+                Token::RBracket => {
+                    *depth = depth.saturating_sub(1);
+                    block_parts.push(Part::Token(token));
+                }
+                _ => block_parts.push(Part::Token(token)),
+            },
+        }
+    }
+
+    if block.is_some() {
+        return Err("unclosed conditional block".to_owned());
+    }
+
+    Ok(parts)
+}
+
+pub enum Dimension<'a> {
+    Block(Vec<&'a str>),
+    Choice {
+        name: &'a str,
+        options: Vec<&'a str>,
+    },
+}
+
+impl Dimension<'_> {
+    /// The number of ways the decision can go
+    pub fn options(&self) -> usize {
+        match self {
+            Dimension::Block(_) => 2,
+            Dimension::Choice { options, .. } => options.len(),
+        }
+    }
+}
+
+fn dimensions_into<'a, T: SqlToken>(
+    parts: &'a [Part<T>],
+    dimensions: &mut Vec<Dimension<'a>>,
+) -> Result<(), String> {
+    for part in parts {
+        match part {
+            Part::Token(_) => {}
+            Part::Block(block) => {
+                let names = block
+                    .iter()
+                    .filter_map(|part| match part {
+                        Part::Token(token) => match token.token() {
+                            Token::Placeholder(placeholder) => Some(&placeholder[1..]),
+                            _ => None,
+                        },
+                        _ => None,
+                    })
+                    .collect();
+                dimensions.push(Dimension::Block(names));
+                dimensions_into(block, dimensions)?;
+            }
+            Part::Choice { name, options } => {
+                let options = options
+                    .iter()
+                    .map(|option| match option.token() {
+                        Token::Word(word) => word.value.as_str(),
+                        _ => unreachable!(),
+                    })
+                    .collect::<Vec<_>>();
+
+                // a choice used again makes the same decision, so it's only listed once
+                let earlier = dimensions.iter().find_map(|dimension| match dimension {
+                    Dimension::Choice {
+                        name: earlier,
+                        options,
+                    } if earlier == name => Some(options),
+                    _ => None,
+                });
+                match earlier {
+                    Some(earlier) if *earlier == options => {}
+                    Some(earlier) => {
+                        return Err(format!(
+                            "choice `{}` is used with different options: [{}] and [{}]",
+                            name,
+                            earlier.join(", "),
+                            options.join(", "),
+                        ))
+                    }
+                    None => dimensions.push(Dimension::Choice { name, options }),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lists the runtime decisions of a template in order, each choice by the place it's first used
+pub fn dimensions<T: SqlToken>(parts: &[Part<T>]) -> Result<Vec<Dimension<'_>>, String> {
+    let mut dimensions = Vec::new();
+    dimensions_into(parts, &mut dimensions)?;
+    Ok(dimensions)
+}
+
+fn select_into<'a, T: SqlToken>(
+    parts: &'a [Part<T>],
+    selection: &mut std::slice::Iter<usize>,
+    chosen: &mut Vec<(&'a str, usize)>,
+    out: &mut Vec<T>,
+    emit: bool,
+) {
+    for part in parts {
+        match part {
+            Part::Token(token) => {
+                if emit {
+                    out.push(token.clone());
+                }
+            }
+            Part::Block(block) => {
+                let include = selection.next() == Some(&1);
+                select_into(block, selection, chosen, out, emit && include);
+            }
+            Part::Choice { name, options } => {
+                let choice = match chosen.iter().find(|(earlier, _)| earlier == name) {
+                    Some(&(_, choice)) => choice,
+                    None => {
+                        let choice = *selection.next().unwrap();
+                        chosen.push((name, choice));
+                        choice
+                    }
+                };
+                let option = &options[choice];
+                if emit {
+                    out.push(option.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Renders a template given a choice for each of its dimensions
+pub fn select<T: SqlToken>(parts: &[Part<T>], selection: &[usize]) -> Vec<T> {
+    let mut out = Vec::new();
+    select_into(
+        parts,
+        &mut selection.iter(),
+        &mut Vec::new(),
+        &mut out,
+        true,
+    );
+    out
+}
+
+fn collect_names<'a, T: SqlToken>(parts: &'a [Part<T>], names: &mut Vec<&'a str>) {
+    for part in parts {
+        match part {
+            Part::Token(token) => {
+                if let Token::Placeholder(placeholder) = token.token() {
+                    names.push(&placeholder[1..]);
+                }
+            }
+            Part::Block(block) => collect_names(block, names),
+            Part::Choice { name, .. } => names.push(name),
+        }
+    }
+}
+
+/// Lists the names of all args used by a template, including the ones in blocks and choices
+pub fn referenced_names<T: SqlToken>(parts: &[Part<T>]) -> Vec<&str> {
+    let mut names = Vec::new();
+    collect_names(parts, &mut names);
+    names
+}
+
+pub fn placeholder_names<T: SqlToken>(tokens: &[T]) -> impl Iterator<Item = &str> {
+    tokens.iter().filter_map(|token| match token.token() {
+        Token::Placeholder(placeholder) => Some(&placeholder[1..]),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlparser::dialect::{MySqlDialect, PostgreSqlDialect, SQLiteDialect};
+
+    use super::*;
+
+    fn located<'a>(sql: &'a str, dialect: &dyn Dialect, sigil: char) -> Vec<(String, &'a str)> {
+        tokenize_located(sql, dialect, sigil)
+            .unwrap()
+            .into_iter()
+            .map(|(token, range)| (token.to_string(), &sql[range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_located() {
+        let sql = "select E'a\\'b', 'ü'\n  -- $1\n  from t where id = $1";
+        let tokens = located(sql, &PostgreSqlDialect {}, '$');
+        assert_eq!(tokens.iter().map(|(_, src)| *src).collect::<String>(), sql);
+        // rendering the token drops the escape, its source keeps it
+        assert!(tokens.contains(&("E'a'b'".to_owned(), "E'a\\'b'")));
+        assert!(tokens.contains(&("$1".to_owned(), "$1")));
+    }
+
+    #[test]
+    fn test_render_located() {
+        let sql = "select E'a\\'b' $col:note!, id::text /* $1: int4 */ where id = $1: int4";
+        let mut tokens = tokenize_located(sql, &PostgreSqlDialect {}, '$')
+            .unwrap()
+            .into_iter()
+            .map(|(token, range)| (token, Some(range)))
+            .collect();
+        assert!(expand_column_overrides(&mut tokens, '"').unwrap());
+        let hints = extract_type_hints(&mut tokens).unwrap();
+        assert_eq!(hints, [("1".to_owned(), "int4".to_owned())]);
+        assert_eq!(
+            render_located(sql, &tokens),
+            "select E'a\\'b' \"note!\", id::text /* $1: int4 */ where id = $1"
+        );
+    }
+
+    #[test]
+    fn test_tokenize_located_placeholders() {
+        let tokens = located("id = :id or ?", &SQLiteDialect {}, ':');
+        assert_eq!(tokens[4], ("$id".to_owned(), ":id"));
+        assert_eq!(tokens[8], ("?".to_owned(), "?"));
+
+        // the `?` is tokenized apart from what follows it
+        let tokens = located("?,?", &PostgreSqlDialect {}, '$');
+        assert_eq!(
+            tokens,
+            [("?", "?"), (",", ","), ("?", "?")].map(|(token, src)| (token.to_owned(), src))
+        );
+
+        // a quote after the `?` doesn't start a string that swallows the rest
+        let tokens = located("data ?'k' and '?' = $1", &PostgreSqlDialect {}, '$');
+        assert_eq!(tokens[2], ("?".to_owned(), "?"));
+        assert_eq!(tokens[3], ("'k'".to_owned(), "'k'"));
+        assert!(tokens.contains(&("'?'".to_owned(), "'?'")));
+        assert!(tokens.contains(&("$1".to_owned(), "$1")));
+
+        let tokens = located("@id?", &MySqlDialect {}, '@');
+        assert_eq!(
+            tokens,
+            [("$id", "@id"), ("?", "?")].map(|(token, src)| (token.to_owned(), src))
+        );
+    }
+}
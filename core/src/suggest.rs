@@ -0,0 +1,70 @@
+//! Suggestions for misspelled names
+
+/// Levenshtein distance between two strings, in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// The candidate closest to `name`, if it's close enough to likely be a typo of it
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, candidate)| {
+            distance <= (name.chars().count().max(candidate.chars().count()) / 3).max(1)
+        })
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("id", ""), 2);
+        assert_eq!(edit_distance("user_id", "usr_id"), 1);
+        assert_eq!(edit_distance("email", "emial"), 2);
+        assert_eq!(edit_distance("naïve", "naive"), 1);
+    }
+
+    #[test]
+    fn test_closest() {
+        assert_eq!(closest("user_id", ["name", "usr_id"]), Some("usr_id"));
+        assert_eq!(closest("user_id", ["user_ids", "usr_id"]), Some("user_ids"));
+        assert_eq!(closest("user_id", []), None);
+    }
+
+    #[test]
+    fn test_closest_ties() {
+        // the first of the equally close candidates wins
+        assert_eq!(closest("id", ["ix", "xd"]), Some("ix"));
+        assert_eq!(closest("id", ["xd", "ix"]), Some("xd"));
+    }
+
+    #[test]
+    fn test_closest_threshold() {
+        // a third of the longer name's length, and at least one edit
+        assert_eq!(closest("id", ["ix"]), Some("ix"));
+        assert_eq!(closest("id", ["xy"]), None);
+        assert_eq!(closest("email", ["emial"]), None);
+        assert_eq!(closest("created_at", ["creatd_a"]), Some("creatd_a"));
+        assert_eq!(closest("created_at", ["creat_a"]), Some("creat_a"));
+        assert_eq!(closest("created_at", ["crat_a"]), None);
+    }
+}
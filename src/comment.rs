@@ -0,0 +1,84 @@
+//! sqlcommenter-style comments pointing queries back to their call sites
+
+/// Comments are added with the `sql-comments` feature, or when `SQLX_NAMED_SQL_COMMENTS` is set
+/// to anything but `0` while the macros are expanded
+pub fn enabled() -> bool {
+    cfg!(feature = "sql-comments")
+        || std::env::var_os("SQLX_NAMED_SQL_COMMENTS")
+            .is_some_and(|val| !val.is_empty() && val != "0")
+}
+
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                char::from(b).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// The location of the macro being expanded, as `/*file='src%2Fdb.rs%3A42'*/`, with the function
+/// it's in when that's known, as in `/*file='src%2Fdb.rs%3A42',fn='get_user'*/`
+pub fn location(function: Option<&str>) -> String {
+    let span = proc_macro::Span::call_site();
+    format(&span.file(), span.line(), function)
+}
+
+// sqlcommenter sorts the keys
+fn format(file: &str, line: usize, function: Option<&str>) -> String {
+    let mut comment = format!("/*file='{}'", encode(&format!("{}:{}", file, line)));
+    if let Some(function) = function {
+        comment.push_str(&format!(",fn='{}'", encode(function)));
+    }
+    comment.push_str("*/");
+    comment
+}
+
+/// Appends the comment to the end of the query, keeping a trailing `;` last
+pub fn apply(sql: String, comment: Option<&str>) -> String {
+    let Some(comment) = comment else {
+        return sql;
+    };
+    // on its own line, so that a trailing `--` comment doesn't swallow it
+    let body = sql.trim_end();
+    match body.strip_suffix(';') {
+        Some(body) => format!("{}\n{};", body, comment),
+        None => format!("{}\n{}", body, comment),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format("src/db.rs", 42, None), "/*file='src%2Fdb.rs%3A42'*/");
+        assert_eq!(
+            format("src/db.rs", 42, Some("get_user")),
+            "/*file='src%2Fdb.rs%3A42',fn='get_user'*/"
+        );
+        assert_eq!(format("it's.rs", 1, None), "/*file='it%27s.rs%3A1'*/");
+    }
+
+    #[test]
+    fn test_apply() {
+        let comment = Some("/*file='a.rs%3A1'*/");
+        assert_eq!(apply("select 1".to_owned(), None), "select 1");
+        assert_eq!(
+            apply("select 1".to_owned(), comment),
+            "select 1\n/*file='a.rs%3A1'*/"
+        );
+        assert_eq!(
+            apply("select 1; \n".to_owned(), comment),
+            "select 1\n/*file='a.rs%3A1'*/;"
+        );
+        assert_eq!(
+            apply("select 1 -- one".to_owned(), comment),
+            "select 1 -- one\n/*file='a.rs%3A1'*/"
+        );
+    }
+}
@@ -0,0 +1,15 @@
+//! Per-crate settings from an optional `sqlx-named.toml` next to `Cargo.toml`
+
+use std::path::Path;
+
+use proc_macro2::Span;
+
+pub use sqlx_named_core::config::{Config, Dialect, UnusedArgs};
+
+/// Reads the config of the crate being compiled, falling back to the defaults without a file
+pub fn load(span: Span) -> syn::Result<Config> {
+    let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") else {
+        return Ok(Config::default());
+    };
+    Config::load(Path::new(&manifest_dir)).map_err(|err| syn::Error::new(span, err))
+}
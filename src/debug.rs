@@ -0,0 +1,98 @@
+//! `SQLX_NAMED_DEBUG` output of what each invocation expands to
+
+use crate::{config::Dialect, meta::Meta};
+
+/// Printing is enabled by setting `SQLX_NAMED_DEBUG` to anything but `0` while the macros are
+/// expanded
+pub fn enabled() -> bool {
+    std::env::var_os("SQLX_NAMED_DEBUG").is_some_and(|val| !val.is_empty() && val != "0")
+}
+
+/// Prints the queries passed on to the wrapped macro with the arg bound to each of their positions
+pub fn print(macro_name: &str, meta: &Meta, dialect: Dialect) {
+    let span = proc_macro::Span::call_site();
+    eprint!(
+        "{}",
+        format(&span.file(), span.line(), macro_name, meta, dialect)
+    );
+}
+
+fn format(file: &str, line: usize, macro_name: &str, meta: &Meta, dialect: Dialect) -> String {
+    let mut out = format!("sqlx-named: {}:{}: {}!\n", file, line, macro_name);
+
+    for (sql, names) in &meta.variants {
+        out += &format!("  {}\n", sql.trim().replace('\n', "\n  "));
+        for (index, name) in names.iter().enumerate() {
+            let position = index + 1;
+            // positional queries are listed with their positions as names
+            if *name == position.to_string() {
+                continue;
+            }
+            let placeholder = dialect
+                .placeholder(position)
+                .unwrap_or_else(|| format!("?#{}", position));
+            out += &format!("    {} = {}\n", placeholder, name);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(variants: &[(&str, &[&str])]) -> Meta {
+        Meta {
+            variants: variants
+                .iter()
+                .map(|(sql, names)| {
+                    let names = names.iter().map(|name| name.to_string()).collect();
+                    (sql.to_string(), names)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_format_postgres() {
+        let meta = meta(&[
+            ("select id from users\nwhere email = $1", &["email"]),
+            ("select id from users", &[]),
+        ]);
+        assert_eq!(
+            format("src/db.rs", 42, "sqlx::query", &meta, Dialect::Postgres),
+            "\
+sqlx-named: src/db.rs:42: sqlx::query!
+  select id from users
+  where email = $1
+    $1 = email
+  select id from users
+"
+        );
+    }
+
+    #[test]
+    fn test_format_mysql() {
+        let meta = meta(&[("select ?, ?, ?", &["id", "name", "id"])]);
+        assert_eq!(
+            format("src/db.rs", 7, "sqlx::query_as", &meta, Dialect::MySql),
+            "\
+sqlx-named: src/db.rs:7: sqlx::query_as!
+  select ?, ?, ?
+    ?#1 = id
+    ?#2 = name
+    ?#3 = id
+"
+        );
+    }
+
+    #[test]
+    fn test_format_positional() {
+        let meta = meta(&[("select $1, $2", &["1", "2"])]);
+        assert_eq!(
+            format("src/db.rs", 1, "sqlx::query", &meta, Dialect::Postgres),
+            "sqlx-named: src/db.rs:1: sqlx::query!\n  select $1, $2\n"
+        );
+    }
+}
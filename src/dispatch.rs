@@ -0,0 +1,306 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{parse_quote_spanned, spanned::Spanned as _, Expr, Ident, Type};
+
+pub struct Hoisted {
+    pub local: Ident,
+    pub binding: TokenStream,
+    pub val: Expr,
+}
+
+/// Evaluates an argument once into a local, which can then be passed to `sqlx` any number of
+/// times through `val`, located at the argument's name so that type errors point at it
+pub fn hoist(name: &str, val: Expr) -> Hoisted {
+    let span = val.span();
+    let local = format_ident!(
+        "__sqlx_named_{}",
+        name,
+        span = Span::mixed_site().located_at(span)
+    );
+
+    let (binding, val) = match val {
+        // type overrides are casts, which move the value, just like `sqlx` does
+        Expr::Cast(cast) if !matches!(*cast.ty, Type::Infer(_)) => {
+            let expr = cast.expr;
+            let ty = cast.ty;
+            (
+                quote! { let #local = #expr; },
+                parse_quote_spanned! { span => #local as #ty },
+            )
+        }
+        Expr::Cast(cast) => {
+            let expr = cast.expr;
+            (
+                quote! { let #local = &(#expr); },
+                parse_quote_spanned! { span => *#local as _ },
+            )
+        }
+        val => (
+            quote! { let #local = &(#val); },
+            parse_quote_spanned! { span => *#local },
+        ),
+    };
+
+    Hoisted {
+        local,
+        binding,
+        val,
+    }
+}
+
+/// Validates an identifier choice against its options, evaluating to the index of the option,
+/// or to the error the query's execution methods return when the value isn't one of them
+pub fn choose(name: &str, local: &Ident, options: &[&str]) -> (TokenStream, Ident) {
+    let choice = format_ident!("__sqlx_named_{}_choice", name, span = Span::mixed_site());
+    let indices = 0..options.len();
+    let expected = options.join(", ");
+
+    let binding = quote! {
+        let #choice = match ::core::convert::AsRef::<str>::as_ref(&#local) {
+            #(#options => ::core::result::Result::Ok(#indices),)*
+            other => ::core::result::Result::Err(::sqlx::Error::Encode(
+                ::core::convert::From::from(::std::format!(
+                    "`{}` is not allowed for `{}`, expected one of: {}",
+                    other,
+                    #name,
+                    #expected,
+                )),
+            )),
+        };
+    };
+
+    (binding, choice)
+}
+
+/// What a query variant is picked by
+pub enum Scrutinee {
+    /// Whether a conditional block is included
+    Block(TokenStream),
+    /// The result of [`choose`]
+    Choice(Ident),
+}
+
+pub struct Variant {
+    /// `true`/`false` for blocks and the index of the option for choices
+    pub pattern: Vec<TokenStream>,
+    pub query: TokenStream,
+}
+
+/// Picks one of the checked queries at runtime, wrapping them in an enum with the execution
+/// methods of `sqlx::query::Query`, `sqlx::query::QueryScalar` and `sqlx::query::Map`
+pub fn dispatch(
+    bindings: Vec<TokenStream>,
+    scrutinees: Vec<Scrutinee>,
+    variants: Vec<Variant>,
+) -> TokenStream {
+    let enum_ident = Ident::new("SqlxNamedVariants", Span::call_site());
+
+    let variant_idents = (0..variants.len())
+        .map(|i| format_ident!("V{}", i))
+        .collect::<Vec<_>>();
+    let query_params = (0..variants.len())
+        .map(|i| format_ident!("Q{}", i))
+        .collect::<Vec<_>>();
+    let mapper_params = (0..variants.len())
+        .map(|i| format_ident!("F{}", i))
+        .collect::<Vec<_>>();
+
+    let arms =
+        variants
+            .iter()
+            .zip(&variant_idents)
+            .map(|(Variant { pattern, query }, variant_ident)| {
+                let pattern = pattern.iter().zip(&scrutinees).map(|(pattern, scrutinee)| {
+                    let ok = quote! { ::core::result::Result::Ok };
+                    match scrutinee {
+                        Scrutinee::Block(_) => pattern.clone(),
+                        Scrutinee::Choice(_) => quote! { #ok(#pattern) },
+                    }
+                });
+                quote! {
+                    (#(#pattern,)*) => #enum_ident::#variant_ident(#query),
+                }
+            });
+
+    // a rejected choice is reported when the query is executed
+    let rejected = scrutinees
+        .iter()
+        .enumerate()
+        .filter(|(_, scrutinee)| matches!(scrutinee, Scrutinee::Choice(_)))
+        .map(|(pos, _)| {
+            let pattern = (0..scrutinees.len()).map(|other| {
+                if other == pos {
+                    quote! { ::core::result::Result::Err(err) }
+                } else {
+                    quote! { _ }
+                }
+            });
+            quote! {
+                (#(#pattern,)*) => #enum_ident::Invalid(err),
+            }
+        })
+        .collect::<Vec<_>>();
+    let exhaustive = rejected.is_empty();
+    let invalid_variant = (!exhaustive).then(|| quote! { Invalid(::sqlx::Error), });
+    let invalid_arm =
+        (!exhaustive).then(|| quote! { Self::Invalid(err) => ::core::result::Result::Err(err), });
+    let fallback = (!exhaustive).then(|| quote! { _ => ::core::unreachable!(), });
+
+    let scrutinees = scrutinees.iter().map(|scrutinee| match scrutinee {
+        Scrutinee::Block(condition) => condition.clone(),
+        Scrutinee::Choice(choice) => quote! { #choice },
+    });
+
+    let query_type = variant_idents
+        .iter()
+        .map(|_| quote! { ::sqlx::query::Query<'q, DB, A> });
+    let scalar_type = variant_idents
+        .iter()
+        .map(|_| quote! { ::sqlx::query::QueryScalar<'q, DB, O, A> });
+    let map_type = mapper_params
+        .iter()
+        .map(|mapper| quote! { ::sqlx::query::Map<'q, DB, #mapper, A> });
+
+    let query_method = |method: Ident, output: TokenStream| {
+        quote! {
+            pub async fn #method<'e, 'c: 'e, E>(
+                self,
+                executor: E,
+            ) -> ::core::result::Result<#output, ::sqlx::Error>
+            where
+                'q: 'e,
+                A: 'e,
+                E: ::sqlx::Executor<'c, Database = DB>,
+            {
+                match self {
+                    #(Self::#variant_idents(query) => query.#method(executor).await,)*
+                    #invalid_arm
+                }
+            }
+        }
+    };
+    let map_method = |method: Ident, output: TokenStream| {
+        quote! {
+            pub async fn #method<'e, 'c: 'e, E>(
+                self,
+                executor: E,
+            ) -> ::core::result::Result<#output, ::sqlx::Error>
+            where
+                'q: 'e,
+                E: 'e + ::sqlx::Executor<'c, Database = DB>,
+                DB: 'e,
+                #(#mapper_params: 'e,)*
+                O: 'e,
+            {
+                match self {
+                    #(Self::#variant_idents(query) => query.#method(executor).await,)*
+                    #invalid_arm
+                }
+            }
+        }
+    };
+
+    let scalar_method = |method: Ident, output: TokenStream| {
+        quote! {
+            pub async fn #method<'e, 'c: 'e, E>(
+                self,
+                executor: E,
+            ) -> ::core::result::Result<#output, ::sqlx::Error>
+            where
+                'q: 'e,
+                E: 'e + ::sqlx::Executor<'c, Database = DB>,
+                DB: 'e,
+                O: 'e,
+                A: 'e,
+            {
+                match self {
+                    #(Self::#variant_idents(query) => query.#method(executor).await,)*
+                    #invalid_arm
+                }
+            }
+        }
+    };
+
+    let query_methods = [
+        query_method(
+            format_ident!("execute"),
+            quote! { <DB as ::sqlx::Database>::QueryResult },
+        ),
+        query_method(
+            format_ident!("fetch_all"),
+            quote! { ::std::vec::Vec<<DB as ::sqlx::Database>::Row> },
+        ),
+        query_method(
+            format_ident!("fetch_one"),
+            quote! { <DB as ::sqlx::Database>::Row },
+        ),
+        query_method(
+            format_ident!("fetch_optional"),
+            quote! { ::core::option::Option<<DB as ::sqlx::Database>::Row> },
+        ),
+    ];
+    let scalar_methods = [
+        scalar_method(format_ident!("fetch_all"), quote! { ::std::vec::Vec<O> }),
+        scalar_method(format_ident!("fetch_one"), quote! { O }),
+        scalar_method(
+            format_ident!("fetch_optional"),
+            quote! { ::core::option::Option<O> },
+        ),
+    ];
+    let map_methods = [
+        map_method(format_ident!("fetch_all"), quote! { ::std::vec::Vec<O> }),
+        map_method(format_ident!("fetch_one"), quote! { O }),
+        map_method(
+            format_ident!("fetch_optional"),
+            quote! { ::core::option::Option<O> },
+        ),
+    ];
+
+    quote! {
+        {
+            #(#bindings)*
+
+            enum #enum_ident<#(#query_params),*> {
+                #(#variant_idents(#query_params),)*
+                #invalid_variant
+            }
+
+            #[allow(dead_code)]
+            impl<'q, DB, A> #enum_ident<#(#query_type),*>
+            where
+                DB: ::sqlx::Database,
+                A: 'q + Send + ::sqlx::IntoArguments<'q, DB>,
+            {
+                #(#query_methods)*
+            }
+
+            #[allow(dead_code)]
+            impl<'q, DB, A, O> #enum_ident<#(#scalar_type),*>
+            where
+                DB: ::sqlx::Database,
+                A: 'q + ::sqlx::IntoArguments<'q, DB>,
+                O: Send + Unpin,
+                (O,): Send + Unpin + for<'r> ::sqlx::FromRow<'r, <DB as ::sqlx::Database>::Row>,
+            {
+                #(#scalar_methods)*
+            }
+
+            #[allow(dead_code)]
+            impl<'q, DB, A, O, #(#mapper_params),*> #enum_ident<#(#map_type),*>
+            where
+                DB: ::sqlx::Database,
+                A: 'q + Send + ::sqlx::IntoArguments<'q, DB>,
+                O: Send + Unpin,
+                #(#mapper_params: FnMut(<DB as ::sqlx::Database>::Row) -> ::core::result::Result<O, ::sqlx::Error> + Send,)*
+            {
+                #(#map_methods)*
+            }
+
+            match (#(#scrutinees,)*) {
+                #(#arms)*
+                #(#rejected)*
+                #fallback
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use proc_macro2::Span;
+use syn::{Expr, Type};
+
+use crate::util::parse_at;
+
+/// Declarations in the leading `--` comments of a query file
+#[derive(Default)]
+pub struct Header {
+    /// `-- $name = expr`
+    pub defaults: Vec<(String, Expr)>,
+    /// `-- param: name Type`
+    pub param_types: Vec<(String, Type)>,
+}
+
+pub fn parse_header(sql: &str, span: Span) -> syn::Result<Header> {
+    let header =
+        sqlx_named_core::header::parse_header(sql).map_err(|err| syn::Error::new(span, err))?;
+
+    Ok(Header {
+        defaults: header
+            .defaults
+            .into_iter()
+            .map(|(name, expr)| {
+                let expr = parse_at::<Expr>(expr, span).map_err(|err| {
+                    syn::Error::new(span, format!("invalid default for `${}`: {}", name, err))
+                })?;
+                Ok((name.to_owned(), expr))
+            })
+            .collect::<syn::Result<_>>()?,
+        param_types: header
+            .param_types
+            .into_iter()
+            .map(|(name, typ)| {
+                let typ = parse_at::<Type>(typ, span).map_err(|err| {
+                    syn::Error::new(span, format!("invalid type for param `{}`: {}", name, err))
+                })?;
+                Ok((name.to_owned(), typ))
+            })
+            .collect::<syn::Result<_>>()?,
+    })
+}
@@ -1,137 +1,740 @@
 #![doc = include_str!("../README.md")]
 
-use std::collections::HashSet;
+use std::{collections::BTreeSet, ops::Range};
 
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use proc_macro2::{Ident, Span};
-use quote::quote;
-use sqlparser::{dialect::PostgreSqlDialect, tokenizer::Tokenizer};
-use syn::parse::Parser as _;
+use quote::{quote, quote_spanned};
+use sqlparser::tokenizer::Token;
+use sqlx_named_core::{sql, suggest};
+use syn::{
+    parse::Parser as _, parse_quote, parse_quote_spanned, spanned::Spanned as _, Expr, Type,
+};
 
-use crate::parse::{ArgType, QueryInput, QueryVariant};
+use crate::{
+    config::{Config, Dialect, UnusedArgs},
+    meta::Meta,
+    parse::{ArgType, QueryInput, QueryVariant, WrapInput},
+    repository::{expand_repository, RepositoryArgs},
+};
 
+const MAX_VARIANTS: usize = 16;
+
+mod comment;
+mod config;
+mod debug;
+mod dispatch;
+mod header;
+mod meta;
 mod parse;
+mod repository;
 mod util;
 
-fn expand(input: QueryInput, out_ident: Ident) -> syn::Result<proc_macro2::TokenStream> {
-    let mut tokens = Tokenizer::new(&PostgreSqlDialect {}, &input.sql)
-        .tokenize()
-        .expect("failed to tokenize sql");
+enum Condition<'a> {
+    Block(BTreeSet<usize>),
+    Choice { index: usize, options: Vec<&'a str> },
+}
+
+/// Expands a query, evaluating to its metadata instead when `describe` is set by `meta!`
+fn expand(
+    input: QueryInput,
+    macro_name: &str,
+    out_macro: syn::Path,
+    describe: bool,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let debug = debug::enabled().then(|| {
+        out_macro
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::")
+    });
+    let config = config::load(input.sql_span)?;
+    let original_sql = input.sql.clone();
+
+    let mut meta = Meta::default();
+    let out = expand_query(input, out_macro, &mut meta, &config)?;
 
-    let unnamed = tokens
+    if let Some(macro_name) = debug {
+        debug::print(&macro_name, &meta, config.dialect);
+    }
+    Ok(meta::attach(
+        out,
+        &meta,
+        macro_name,
+        &original_sql,
+        describe,
+    ))
+}
+
+fn expand_query(
+    input: QueryInput,
+    out_macro: syn::Path,
+    meta: &mut Meta,
+    config: &Config,
+) -> syn::Result<proc_macro2::TokenStream> {
+    // the tokens keep their source, so that a positional query is only changed where it's
+    // rewritten
+    let mut located = sql::tokenize_located(
+        &input.sql,
+        &*config.dialect.tokenizer_dialect(),
+        config.sigil,
+    )
+    .map_err(|err| syn::Error::new(input.sql_span, err))?
+    .into_iter()
+    .map(|(token, range)| (token, Some(range)))
+    .collect::<Vec<_>>();
+    let overrides = sql::expand_column_overrides(&mut located, config.dialect.identifier_quote())
+        .map_err(|err| syn::Error::new(input.sql_span, err))?;
+    let hints = sql::extract_type_hints(&mut located)
+        .map_err(|err| syn::Error::new(input.sql_span, err))?;
+    let hints = hints
+        .into_iter()
+        .map(|(name, typ)| {
+            let typ = util::parse_at::<Type>(&typ, input.sql_span).map_err(|err| {
+                syn::Error::new(
+                    input.sql_span,
+                    format!("invalid type hint for `${}`: {}", name, err),
+                )
+            })?;
+            Ok((name, typ))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let unnamed = located
         .iter()
-        .filter_map(|token| match token {
-            sqlparser::tokenizer::Token::Placeholder(placeholder) => Some(placeholder),
+        .filter_map(|(token, _)| match token {
+            Token::Placeholder(placeholder) => Some(placeholder),
             _ => None,
         })
-        .all(|placeholder| placeholder.chars().skip(1).all(|c| c.is_ascii_digit()));
+        .all(|placeholder| sql::is_positional(placeholder));
+
+    let mut bindings = input.bindings;
+    let comment = config
+        .comments
+        .unwrap_or_else(comment::enabled)
+        .then(|| comment::location(input.function.as_deref()));
 
     let (sql, args) = if unnamed {
-        let args = input
+        if !input.param_types.is_empty() {
+            let params = input
+                .param_types
+                .iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+            return Err(syn::Error::new(
+                input.sql_span,
+                format!("unknown params: {:?}", params.as_slice()),
+            ));
+        }
+
+        let rewritten = overrides || !hints.is_empty();
+        let mut args = input
             .args
             .into_iter()
             .map(|arg| match arg.typ {
                 ArgType::Named(_) => Err(syn::Error::new_spanned(arg.val, "named arg")),
+                ArgType::Fields => Err(syn::Error::new_spanned(
+                    arg.val,
+                    "field splats can only be used with named placeholders",
+                )),
+                ArgType::Default(name) => Err(syn::Error::new_spanned(
+                    arg.val,
+                    format!(
+                        "the default for `{}` can only be used with named placeholders",
+                        name
+                    ),
+                )),
                 ArgType::Unnamed(_) => Ok(arg.val),
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        (input.sql, args)
+        for (name, typ) in hints {
+            let index = name.parse::<usize>().ok().and_then(|n| n.checked_sub(1));
+            if let Some(val) = index.and_then(|index| args.get_mut(index)) {
+                *val = parse::ascribe(val.clone(), typ)?;
+            }
+        }
+
+        let sql = if rewritten {
+            sql::render_located(&input.sql, &located)
+        } else {
+            input.sql
+        };
+        let sql = comment::apply(sql, comment.as_deref());
+
+        let positions = (1..=args.len()).map(|position| position.to_string());
+        meta.variants.push((sql.clone(), positions.collect()));
+
+        (sql, args)
     } else {
-        let named_args = input
-            .args
-            .into_iter()
-            .map(|arg| {
-                let name = match arg.typ {
-                    ArgType::Named(name) => Ok(name),
-                    ArgType::Unnamed(name) => {
-                        name.ok_or_else(|| syn::Error::new_spanned(&arg.val, "unnamed arg"))
+        let mut named_args = IndexMap::new();
+        let mut defaults = IndexMap::new();
+        let mut fields = None;
+
+        for arg in input.args {
+            let name = match arg.typ {
+                ArgType::Named(name) => name,
+                ArgType::Unnamed(name) => {
+                    name.ok_or_else(|| syn::Error::new_spanned(&arg.val, "unnamed arg"))?
+                }
+                // defaults given at the call site come before the ones from the file header
+                ArgType::Default(name) => {
+                    defaults.entry(name).or_insert(arg.val);
+                    continue;
+                }
+                ArgType::Fields => {
+                    if fields.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            arg.val,
+                            "only one field splat is allowed",
+                        ));
                     }
-                }?;
-                Ok::<_, syn::Error>((name, arg.val))
-            })
-            .collect::<Result<IndexMap<_, _>, _>>()?;
+                    fields = Some(arg.val);
+                    continue;
+                }
+            };
+
+            if named_args.contains_key(&name) {
+                return Err(syn::Error::new_spanned(
+                    arg.val,
+                    format!("arg given more than once: {}", name),
+                ));
+            }
+            named_args.insert(name, arg.val);
+        }
+
+        let tokens = sql::expand_optional_predicates(located)
+            .map_err(|err| syn::Error::new(input.sql_span, err))?;
+        let parts =
+            sql::parse_template(tokens).map_err(|err| syn::Error::new(input.sql_span, err))?;
+        let dimensions =
+            sql::dimensions(&parts).map_err(|err| syn::Error::new(input.sql_span, err))?;
 
-        let mut used = HashSet::new();
+        let referenced = sql::referenced_names(&parts);
 
-        for token in &mut tokens {
-            if let sqlparser::tokenizer::Token::Placeholder(placeholder) = token {
-                let arg = &placeholder[1..];
-                let Some(index) = named_args.get_index_of(arg) else {
-                    panic!("arg not given: {}", arg);
+        // the parent of a field splat is evaluated once, before the query, and its fields are
+        // located at it so that type errors point at the splat
+        let fields = fields.map(|parent| {
+            let span = parent.span();
+            let local = Ident::new("__sqlx_named_fields", Span::mixed_site().located_at(span));
+            bindings.push(quote! { let #local = &(#parent); });
+            (local, span)
+        });
+
+        // defaults are given for names the splatted value doesn't have, so they come first
+        for &name in &referenced {
+            if named_args.contains_key(name) {
+                continue;
+            }
+            if let Some(val) = defaults.shift_remove(name) {
+                named_args.insert(name.to_owned(), val);
+            } else if let Some((local, span)) = &fields {
+                // keywords are taken as raw identifiers, except for the ones that can't be raw
+                let mut field = match syn::parse_str::<Ident>(name) {
+                    Ok(field) => field,
+                    Err(_) if !matches!(name, "self" | "Self" | "super" | "crate" | "_") => {
+                        Ident::new_raw(name, *span)
+                    }
+                    Err(_) => {
+                        return Err(syn::Error::new(
+                            *span,
+                            format!(
+                                "`{}{}` can't be taken from a field, give it as a named arg",
+                                config.sigil, name
+                            ),
+                        ))
+                    }
                 };
-                used.insert(arg.to_owned());
-                *placeholder = format!("${}", index + 1);
+                field.set_span(*span);
+                named_args.insert(
+                    name.to_owned(),
+                    parse_quote_spanned! { *span => #local.#field },
+                );
+            }
+        }
+
+        let unknown = input
+            .param_types
+            .iter()
+            .map(|(name, _)| name)
+            .filter(|name| !referenced.contains(&name.as_str()))
+            .collect::<Vec<_>>();
+
+        if !unknown.is_empty() {
+            return Err(syn::Error::new(
+                input.sql_span,
+                format!("unknown params: {:?}", unknown.as_slice()),
+            ));
+        }
+
+        for (name, typ) in input.param_types.into_iter().chain(hints) {
+            if let Some(val) = named_args.get_mut(&name) {
+                *val = parse::ascribe(val.clone(), typ)?;
             }
         }
 
-        let unused = named_args
+        let variant_count = dimensions
+            .iter()
+            .map(sql::Dimension::options)
+            .product::<usize>();
+
+        if variant_count > MAX_VARIANTS {
+            return Err(syn::Error::new(
+                input.sql_span,
+                format!(
+                    "too many query variants ({}), at most {} are supported; \
+                     build the query at runtime with `sqlx::QueryBuilder` instead",
+                    variant_count, MAX_VARIANTS,
+                ),
+            ));
+        }
+
+        let mut unused = named_args
             .keys()
-            .filter(|arg| !used.contains(*arg))
+            .map(String::as_str)
+            .filter(|name| !referenced.contains(name))
             .collect::<Vec<_>>();
+        let missing = referenced
+            .iter()
+            .copied()
+            .filter(|name| !named_args.contains_key(*name))
+            .collect::<IndexSet<_>>();
+
+        let level = input.unused_args.unwrap_or(config.unused_args);
+
+        if !missing.is_empty() {
+            // a missing arg is often a misspelling of an unused one, which is then suggested
+            // instead of being reported on its own
+            let mut errors = missing.into_iter().map(|name| {
+                let message = match suggest::closest(name, unused.iter().copied()) {
+                    Some(suggestion) => {
+                        unused.retain(|name| *name != suggestion);
+                        format!("arg not given: `{}`, did you mean `{}`?", name, suggestion)
+                    }
+                    None => format!("arg not given: `{}`", name),
+                };
+                syn::Error::new(input.sql_span, message)
+            });
+            let mut err = errors.next().unwrap();
+            errors.for_each(|other| err.combine(other));
+
+            if matches!(level, UnusedArgs::Deny) {
+                for name in unused {
+                    err.combine(unused_arg_error(name, &named_args[name]));
+                }
+            }
+            return Err(err);
+        }
 
         if !unused.is_empty() {
-            panic!("unused args: {:?}", unused.as_slice());
+            match level {
+                UnusedArgs::Deny => {
+                    let mut errors = unused
+                        .iter()
+                        .map(|name| unused_arg_error(name, &named_args[*name]));
+                    let mut err = errors.next().unwrap();
+                    errors.for_each(|other| err.combine(other));
+                    return Err(err);
+                }
+                // stable proc macros can't emit warnings, but using a deprecated item can
+                UnusedArgs::Warn => {
+                    let note = format!("unused args: {:?}", unused.as_slice());
+                    bindings.push(quote_spanned! { input.sql_span =>
+                        #[deprecated(note = #note)]
+                        #[allow(non_upper_case_globals)]
+                        const unused_args: () = ();
+                        let _ = unused_args;
+                    });
+                }
+                UnusedArgs::Allow => {}
+            }
+        }
+
+        let arg_index = |arg: &str| named_args.get_index_of(arg).unwrap();
+
+        let conditions = dimensions
+            .iter()
+            .map(|dimension| match dimension {
+                sql::Dimension::Block(names) => {
+                    if names.is_empty() {
+                        return Err(syn::Error::new(
+                            input.sql_span,
+                            "conditional blocks must contain a placeholder",
+                        ));
+                    }
+                    Ok(Condition::Block(
+                        names.iter().map(|name| arg_index(name)).collect(),
+                    ))
+                }
+                sql::Dimension::Choice { name, options } => Ok(Condition::Choice {
+                    index: arg_index(name),
+                    options: options.clone(),
+                }),
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        let variants = (0..variant_count)
+            .map(|mut variant| {
+                let selection = dimensions
+                    .iter()
+                    .map(|dimension| {
+                        let choice = variant % dimension.options();
+                        variant /= dimension.options();
+                        choice
+                    })
+                    .collect::<Vec<_>>();
+                let (sql, indices) = render(
+                    &input.sql,
+                    sql::select(&parts, &selection),
+                    &named_args,
+                    config.dialect,
+                    input.bind_each || config.bind_each,
+                );
+                (selection, comment::apply(sql, comment.as_deref()), indices)
+            })
+            .collect::<Vec<_>>();
+
+        meta.variants
+            .extend(variants.iter().map(|(_, sql, indices)| {
+                let names = indices.iter().map(|&index| {
+                    let (name, _) = named_args.get_index(index).unwrap();
+                    name.clone()
+                });
+                (sql.clone(), names.collect())
+            }));
+
+        if variants.len() > 1 {
+            let as_type = input.as_type.map(|as_type| quote! { #as_type, });
+            let names = named_args.keys().cloned().collect::<Vec<_>>();
+
+            let hoisted = named_args
+                .into_iter()
+                .map(|(name, val)| dispatch::hoist(&name, val))
+                .collect::<Vec<_>>();
+
+            bindings.extend(hoisted.iter().map(|hoisted| hoisted.binding.clone()));
+
+            let scrutinees = conditions
+                .iter()
+                .map(|condition| match condition {
+                    Condition::Block(indices) => {
+                        let locals = indices.iter().map(|&index| &hoisted[index].local);
+                        dispatch::Scrutinee::Block(quote! { #(#locals.is_some())&&* })
+                    }
+                    Condition::Choice { index, options } => {
+                        let (binding, choice) =
+                            dispatch::choose(&names[*index], &hoisted[*index].local, options);
+                        bindings.push(binding);
+                        dispatch::Scrutinee::Choice(choice)
+                    }
+                })
+                .collect();
+
+            let variants = variants
+                .into_iter()
+                .map(|(selection, sql, indices)| {
+                    let args = indices.iter().map(|&index| &hoisted[index].val);
+                    let pattern = selection
+                        .into_iter()
+                        .zip(&conditions)
+                        .map(|(choice, condition)| match condition {
+                            Condition::Block(_) => {
+                                let include = choice == 1;
+                                quote! { #include }
+                            }
+                            Condition::Choice { .. } => quote! { #choice },
+                        })
+                        .collect();
+                    dispatch::Variant {
+                        pattern,
+                        query: quote! { #out_macro!(#as_type #sql, #(#args),*) },
+                    }
+                })
+                .collect();
+
+            return Ok(dispatch::dispatch(bindings, scrutinees, variants));
         }
 
-        let sql = tokens
+        let [(_, sql, indices)] = <[_; 1]>::try_from(variants).unwrap();
+
+        // an arg bound more than once is evaluated once, before the query
+        let repeated = indices
+            .iter()
+            .filter(|&index| indices.iter().filter(|&other| other == index).count() > 1)
+            .collect::<BTreeSet<_>>();
+        let vals = named_args
             .into_iter()
-            .map(|token| token.to_string())
-            .collect::<Vec<_>>()
-            .concat();
+            .enumerate()
+            .map(|(index, (name, val))| {
+                if !repeated.contains(&index) {
+                    return val;
+                }
+                let hoisted = dispatch::hoist(&name, val);
+                bindings.push(hoisted.binding);
+                hoisted.val
+            })
+            .collect::<Vec<_>>();
+        let args = indices
+            .iter()
+            .map(|&index| vals[index].clone())
+            .collect::<Vec<_>>();
 
-        (sql, named_args.into_values().collect::<Vec<_>>())
+        (sql, args)
     };
 
     let as_type = input.as_type.map(|as_type| quote! { #as_type, });
+    let query = quote! {
+        #out_macro!(#as_type #sql, #(#args),*)
+    };
 
-    Ok(quote! {
-        ::sqlx::#out_ident!(#as_type #sql, #(#args),*)
+    Ok(if bindings.is_empty() {
+        query
+    } else {
+        quote! {
+            {
+                #(#bindings)*
+                #query
+            }
+        }
     })
 }
 
+/// Replaces named placeholders with positional ones, returning the sql and the indices of the
+/// args to pass, in the order of their positions.
+///
+/// Dialects without numbered placeholders get an arg for every occurrence, as does every dialect
+/// with `bind_each`
+/// Numbers the placeholders for the dialect, keeping the rest of the query as it was written
+fn render(
+    source: &str,
+    mut tokens: Vec<(Token, Option<Range<usize>>)>,
+    named_args: &IndexMap<String, Expr>,
+    dialect: Dialect,
+    bind_each: bool,
+) -> (String, Vec<usize>) {
+    let mut used = BTreeSet::new();
+    let mut occurrences = Vec::new();
+
+    for arg in sql::placeholder_names(&tokens) {
+        let index = named_args.get_index_of(arg).unwrap();
+        used.insert(index);
+        occurrences.push(index);
+    }
+
+    let numbered = !bind_each && dialect.placeholder(1).is_some();
+    let indices = if numbered {
+        used.into_iter().collect::<Vec<_>>()
+    } else {
+        occurrences
+    };
+
+    let mut occurrence = 0;
+    for (token, range) in &mut tokens {
+        if let Token::Placeholder(placeholder) = token {
+            *range = None;
+            *placeholder = if numbered {
+                let index = named_args.get_index_of(&placeholder[1..]).unwrap();
+                let position = indices.binary_search(&index).unwrap();
+                dialect.placeholder(position + 1).unwrap()
+            } else {
+                occurrence += 1;
+                dialect
+                    .placeholder(occurrence)
+                    .unwrap_or_else(|| "?".to_owned())
+            };
+        }
+    }
+
+    (sql::render_located(source, &tokens), indices)
+}
+
+fn unused_arg_error(name: &str, val: &Expr) -> syn::Error {
+    syn::Error::new_spanned(val, format!("unused arg: `{}`", name))
+}
+
+/// Combined errors expand to several `compile_error!`s, which need a block in expression position
+fn expr_error(err: syn::Error) -> proc_macro::TokenStream {
+    let errors = err.to_compile_error();
+    quote! { { #errors } }.into()
+}
+
 fn query_generic(
+    macro_name: &str,
     variant: QueryVariant,
-    out_ident: Ident,
+    out_macro: syn::Path,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
     match variant
         .parse_query()
         .parse(input)
-        .and_then(|input| expand(input, out_ident))
+        .and_then(|input| expand(input, macro_name, out_macro, false))
     {
         Ok(out) => out.into(),
-        Err(err) => proc_macro::TokenStream::from(err.to_compile_error()),
+        Err(err) => expr_error(err),
     }
 }
 
-macro_rules! def_variant {
-    ($ident:ident) => {
-        #[proc_macro]
-        pub fn $ident(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-            const IDENT_STR: &str = stringify!($ident);
-            let variant = QueryVariant {
-                file: const_str::contains!(IDENT_STR, "file"),
-                as_type: const_str::contains!(IDENT_STR, "as"),
-            };
-            let out_ident = Ident::new(
-                const_str::replace!(IDENT_STR, "_file", ""),
-                Span::call_site(),
-            );
-            query_generic(variant, out_ident, input)
+/// The variant of a query macro, and the `sqlx` macro it wraps
+macro_rules! variant {
+    ($ident:ident) => {{
+        const IDENT_STR: &str = stringify!($ident);
+        let variant = QueryVariant {
+            file: const_str::contains!(IDENT_STR, "file"),
+            as_type: const_str::contains!(IDENT_STR, "as"),
+        };
+        let out_ident = Ident::new(
+            const_str::replace!(IDENT_STR, "_file", ""),
+            Span::call_site(),
+        );
+        let out_macro: syn::Path = parse_quote! { ::sqlx::#out_ident };
+        (variant, out_macro)
+    }};
+}
+
+macro_rules! def_variants {
+    ($($ident:ident,)*) => {
+        $(
+            #[proc_macro]
+            pub fn $ident(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+                let (variant, out_macro) = variant!($ident);
+                query_generic(stringify!($ident), variant, out_macro, input)
+            }
+        )*
+
+        /// The query macro with the given name, for `meta!`
+        #[cfg(feature = "meta")]
+        fn variant_by_name(name: &str) -> Option<(QueryVariant, syn::Path)> {
+            match name {
+                $(stringify!($ident) => Some(variant!($ident)),)*
+                _ => None,
+            }
         }
     };
 }
 
-def_variant!(query);
-def_variant!(query_as);
-def_variant!(query_as_unchecked);
-def_variant!(query_file);
-def_variant!(query_file_as);
-def_variant!(query_file_as_unchecked);
-def_variant!(query_file_scalar);
-def_variant!(query_file_scalar_unchecked);
-def_variant!(query_file_unchecked);
-def_variant!(query_scalar);
-def_variant!(query_scalar_unchecked);
-def_variant!(query_unchecked);
+def_variants! {
+    query,
+    query_as,
+    query_as_unchecked,
+    query_file,
+    query_file_as,
+    query_file_as_unchecked,
+    query_file_scalar,
+    query_file_scalar_unchecked,
+    query_file_unchecked,
+    query_scalar,
+    query_scalar_unchecked,
+    query_unchecked,
+}
+
+/// Rewrites a query for any macro taking an optional type, the sql and positional args
+#[proc_macro]
+pub fn wrap(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as WrapInput);
+    let macro_name = input
+        .out_macro
+        .segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+    match expand(input.query, &macro_name, input.out_macro, false) {
+        Ok(out) => out.into(),
+        Err(err) => expr_error(err),
+    }
+}
+
+#[proc_macro_attribute]
+pub fn repository(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let args = syn::parse_macro_input!(args as RepositoryArgs);
+    let item = syn::parse_macro_input!(input as syn::ItemTrait);
+    match expand_repository(args, item) {
+        Ok(out) => out.into(),
+        Err(err) => proc_macro::TokenStream::from(err.to_compile_error()),
+    }
+}
+
+/// Describes the queries a query macro invocation would run, without running them
+#[cfg(feature = "meta")]
+#[proc_macro]
+pub fn meta(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let mac = syn::parse_macro_input!(input as syn::Macro);
+    let macro_name = mac
+        .path
+        .segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+
+    let Some((variant, out_macro)) = variant_by_name(&macro_name) else {
+        return syn::Error::new_spanned(&mac.path, "expected one of the query macros")
+            .to_compile_error()
+            .into();
+    };
+
+    match variant
+        .parse_query()
+        .parse2(mac.tokens)
+        .and_then(|input| expand(input, &macro_name, out_macro, true))
+    {
+        Ok(out) => out.into(),
+        Err(err) => expr_error(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::*;
+
+    fn expand_with(config: Config, tokens: proc_macro2::TokenStream) -> (String, Meta) {
+        let variant = QueryVariant {
+            file: false,
+            as_type: false,
+        };
+        let input = variant.parse_query().parse2(tokens).unwrap();
+        let mut meta = Meta::default();
+        let out = expand_query(input, parse_quote! { ::sqlx::query }, &mut meta, &config).unwrap();
+        (out.to_string(), meta)
+    }
+
+    #[test]
+    fn test_mysql_repeated_arg() {
+        let config = Config {
+            dialect: Dialect::MySql,
+            comments: Some(false),
+            ..Config::default()
+        };
+        let (out, meta) = expand_with(
+            config,
+            quote! { "select $id, $name, $id", id = next_id(), name },
+        );
+
+        assert_eq!(meta.variants[0].0, "select ?, ?, ?");
+        assert_eq!(meta.variants[0].1, ["id", "name", "id"]);
+        // bound twice, but evaluated once
+        assert_eq!(out.matches("next_id ()").count(), 1);
+    }
+
+    #[test]
+    fn test_sqlite_sigil() {
+        let config = Config {
+            dialect: Dialect::Sqlite,
+            sigil: ':',
+            comments: Some(false),
+            ..Config::default()
+        };
+        let (_, meta) = expand_with(
+            config,
+            quote! { "select :id, :name, :id", id = 1, name = "x" },
+        );
+
+        assert_eq!(meta.variants[0].0, "select ?1, ?2, ?1");
+        assert_eq!(meta.variants[0].1, ["id", "name"]);
+    }
+}
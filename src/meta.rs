@@ -0,0 +1,57 @@
+use proc_macro2::TokenStream;
+#[cfg(feature = "meta")]
+use quote::quote;
+
+/// The queries `expand` renders, kept for `meta!`
+#[derive(Default)]
+pub struct Meta {
+    /// The sql of each variant, with the names of its args in the order of their positions
+    pub variants: Vec<(String, Vec<String>)>,
+}
+
+/// Declares the metadata of a query in a hidden const next to it, which `meta!` evaluates to
+/// in place of the query
+#[cfg(feature = "meta")]
+pub fn attach(
+    out: TokenStream,
+    meta: &Meta,
+    macro_name: &str,
+    original_sql: &str,
+    describe: bool,
+) -> TokenStream {
+    let variants = meta.variants.iter().map(|(sql, names)| {
+        let positions = 1..=names.len();
+        quote! {
+            ::sqlx_named_core::meta::QueryVariantMeta {
+                sql: #sql,
+                params: &[#((#names, #positions)),*],
+            }
+        }
+    });
+    let value = if describe {
+        quote! { __SQLX_NAMED_META }
+    } else {
+        out
+    };
+
+    quote! {
+        {
+            #[doc(hidden)]
+            #[allow(dead_code)]
+            const __SQLX_NAMED_META: ::sqlx_named_core::meta::QueryMeta =
+                ::sqlx_named_core::meta::QueryMeta {
+                    macro_name: #macro_name,
+                    original_sql: #original_sql,
+                    variants: &[#(#variants),*],
+                    file: ::core::file!(),
+                    line: ::core::line!(),
+                };
+            #value
+        }
+    }
+}
+
+#[cfg(not(feature = "meta"))]
+pub fn attach(out: TokenStream, _: &Meta, _: &str, _: &str, _: bool) -> TokenStream {
+    out
+}
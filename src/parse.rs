@@ -1,20 +1,31 @@
-use proc_macro2::TokenStream;
-use quote::ToTokens;
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
 use syn::{
     braced, parenthesized,
     parse::{Parse, ParseStream, Parser},
     parse_quote,
     punctuated::Punctuated,
+    spanned::Spanned as _,
     token::{Brace, Paren},
-    AngleBracketedGenericArguments, Expr, Ident, LitInt, LitStr, Member, Token, Type, UnOp,
+    AngleBracketedGenericArguments, Attribute, Expr, ExprClosure, Ident, Index, LitInt, LitStr,
+    Member, Token, Type, TypeTuple,
 };
 
-use crate::util::read_file_src;
+use sqlx_named_core::args::{get_name, pun_name};
+
+use crate::{
+    config::UnusedArgs,
+    header::parse_header,
+    util::{locate_at_name, read_file_src},
+};
 
 #[derive(Debug)]
 pub enum ArgType {
     Unnamed(Option<String>),
     Named(String),
+    Default(String),
+    /// `..parent`, supplying fields for all placeholders without an arg
+    Fields,
 }
 
 #[derive(Debug)]
@@ -27,7 +38,18 @@ pub struct Arg {
 pub struct QueryInput {
     pub as_type: Option<Type>,
     pub sql: String,
+    pub sql_span: Span,
     pub args: Punctuated<Arg, Token![,]>,
+    /// Types declared for named args outside of the call
+    pub param_types: Vec<(String, Type)>,
+    /// Statements binding locals used by the args
+    pub bindings: Vec<TokenStream>,
+    /// `#[allow(unused_args)]`, `#[warn(unused_args)]` or `#[deny(unused_args)]` before the query
+    pub unused_args: Option<UnusedArgs>,
+    /// `#[bind_each]` before the query, binding every occurrence of a named arg separately
+    pub bind_each: bool,
+    /// The function the query runs in, when it's known, for call site comments
+    pub function: Option<String>,
 }
 
 pub struct QueryVariant {
@@ -38,6 +60,11 @@ pub struct QueryVariant {
 impl QueryVariant {
     pub fn parse_query(self) -> impl Parser<Output = QueryInput> {
         move |input: ParseStream| {
+            let QueryAttrs {
+                unused_args,
+                bind_each,
+            } = parse_attrs(input)?;
+
             let as_type = if self.as_type {
                 let as_type = input.parse()?;
                 input.parse::<Token![,]>()?;
@@ -53,133 +80,475 @@ impl QueryVariant {
                 lit_str.value()
             };
 
-            let args = if input.is_empty() {
-                Default::default()
+            let (mut args, bindings) = if input.is_empty() {
+                (Punctuated::new(), Vec::new())
             } else {
                 input.parse::<Token![,]>()?;
                 extract_all(input.parse_terminated(RawArg::parse, Token![,])?)?
             };
 
-            Ok(QueryInput { as_type, sql, args })
+            let mut param_types = Vec::new();
+
+            if self.file {
+                let header = parse_header(&sql, lit_str.span())?;
+                for (name, val) in header.defaults {
+                    args.push(Arg {
+                        typ: ArgType::Default(name),
+                        val,
+                    });
+                }
+                param_types = header.param_types;
+            }
+
+            Ok(QueryInput {
+                as_type,
+                sql,
+                sql_span: lit_str.span(),
+                args,
+                param_types,
+                bindings,
+                unused_args,
+                bind_each,
+                function: None,
+            })
+        }
+    }
+}
+
+/// `wrap!(path::to::macro, Type?, "sql", args...)`
+pub struct WrapInput {
+    pub out_macro: syn::Path,
+    pub query: QueryInput,
+}
+
+impl Parse for WrapInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let out_macro = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        // the type is optional, since the wrapped macro is unknown
+        let fork = input.fork();
+        parse_attrs(&fork)?;
+        let variant = QueryVariant {
+            file: false,
+            as_type: !fork.peek(LitStr),
+        };
+
+        Ok(Self {
+            out_macro,
+            query: variant.parse_query().parse2(input.parse()?)?,
+        })
+    }
+}
+
+type Extracted = (Punctuated<Arg, Token![,]>, Vec<TokenStream>);
+
+struct QueryAttrs {
+    unused_args: Option<UnusedArgs>,
+    bind_each: bool,
+}
+
+/// `#[allow(unused_args)]`, `#[warn(unused_args)]`, `#[deny(unused_args)]` and `#[bind_each]`
+/// before the query
+fn parse_attrs(input: ParseStream) -> syn::Result<QueryAttrs> {
+    let mut level = None;
+    let mut bind_each = false;
+
+    for attr in input.call(Attribute::parse_outer)? {
+        let path = attr.path();
+        if path.is_ident("bind_each") {
+            attr.meta.require_path_only()?;
+            bind_each = true;
+            continue;
+        }
+        level = Some(if path.is_ident("allow") {
+            UnusedArgs::Allow
+        } else if path.is_ident("warn") {
+            UnusedArgs::Warn
+        } else if path.is_ident("deny") {
+            UnusedArgs::Deny
+        } else {
+            return Err(syn::Error::new_spanned(
+                path,
+                "unsupported attribute, expected `allow`, `warn`, `deny` or `bind_each`",
+            ));
+        });
+
+        let lint = attr.parse_args::<Ident>()?;
+        if lint != "unused_args" {
+            return Err(syn::Error::new_spanned(
+                lint,
+                "unknown lint, expected `unused_args`",
+            ));
         }
     }
+
+    Ok(QueryAttrs {
+        unused_args: level,
+        bind_each,
+    })
 }
 
-fn extract_all(raw_seq: Punctuated<RawArg, Token![,]>) -> syn::Result<Punctuated<Arg, Token![,]>> {
+/// Flattens the raw args, also returning the statements that have to run before the query
+fn extract_all(raw_seq: Punctuated<RawArg, Token![,]>) -> syn::Result<Extracted> {
     let mut out = Punctuated::new();
+    let mut bindings = Vec::new();
     for pair in raw_seq.into_pairs() {
         let (raw, comma) = pair.into_tuple();
-        raw.extract(&mut out)?;
-        if let Some(comma) = (!out.trailing_punct()).then_some(comma).flatten() {
+        raw.extract(&mut out, &mut bindings)?;
+        if let Some(comma) = (!out.empty_or_trailing()).then_some(comma).flatten() {
             out.push_punct(comma);
         }
     }
-    Ok(out)
+    Ok((out, bindings))
 }
 
 enum RawArg {
     Single(Expr),
+    Default {
+        name: Ident,
+        _question_token: Token![?],
+        _eq_token: Token![=],
+        val: Expr,
+    },
     Splat {
         _splat_token: Token![..],
         parent: Expr,
+        /// `..parent as prefix_ { ... }`
+        prefix: Option<Ident>,
+        /// `..parent? { ... }`, with `parent` being an `Option`
+        optional: bool,
         _brace_token: Brace,
         children: Punctuated<RawChild, Token![,]>,
     },
+    Fields {
+        _splat_token: Token![..],
+        parent: Expr,
+    },
+    /// `..parent as (a, (b, _))`
+    Tuple {
+        _splat_token: Token![..],
+        parent: Expr,
+        names: TypeTuple,
+    },
 }
 
-fn get_name(expr: &Expr) -> Option<String> {
-    match expr {
-        Expr::Path(path) => Some(path.path.get_ident()?.to_string()),
-        Expr::Reference(rf) => get_name(&rf.expr),
-        Expr::Await(wait) => get_name(&wait.base),
-        Expr::Try(tr) => get_name(&tr.expr),
-        Expr::Unary(un) if matches!(&un.op, UnOp::Deref(_)) => get_name(&un.expr),
-        _ => None,
+/// Adds a type override to an arg, failing if it already has a different one
+pub fn ascribe(val: Expr, typ: Type) -> syn::Result<Expr> {
+    match val {
+        Expr::Cast(mut cast) if matches!(*cast.ty, Type::Infer(_)) => {
+            cast.ty = Box::new(typ);
+            Ok(Expr::Cast(cast))
+        }
+        Expr::Cast(cast) => {
+            if cast.ty.to_token_stream().to_string() == typ.to_token_stream().to_string() {
+                Ok(Expr::Cast(cast))
+            } else {
+                Err(syn::Error::new_spanned(
+                    &cast.ty,
+                    format!(
+                        "type override conflicts with the declared type `{}`",
+                        typ.to_token_stream(),
+                    ),
+                ))
+            }
+        }
+        val @ (Expr::Path(_)
+        | Expr::Lit(_)
+        | Expr::Field(_)
+        | Expr::Call(_)
+        | Expr::MethodCall(_)
+        | Expr::Paren(_)) => Ok(parse_quote! { #val as #typ }),
+        val => Ok(parse_quote! { (#val) as #typ }),
     }
 }
 
 impl RawArg {
-    fn extract(self, out: &mut Punctuated<Arg, Token![,]>) -> syn::Result<()> {
+    fn extract(
+        self,
+        out: &mut Punctuated<Arg, Token![,]>,
+        bindings: &mut Vec<TokenStream>,
+    ) -> syn::Result<()> {
         match self {
             RawArg::Single(expr) => {
                 let arg = match expr {
-                    Expr::Path(path) => {
-                        let expr = Expr::Path(path);
-                        Arg {
-                            typ: ArgType::Unnamed(get_name(&expr)),
-                            val: expr,
-                        }
-                    }
-                    Expr::Cast(cast) => Arg {
-                        typ: ArgType::Unnamed(get_name(&cast.expr)),
-                        val: Expr::Cast(cast),
-                    },
                     Expr::Assign(ass) => Arg {
                         typ: ArgType::Named(get_name(&ass.left).ok_or_else(|| {
-                            syn::Error::new_spanned(ass.left, "invalid arg name")
+                            syn::Error::new_spanned(&ass.left, "invalid arg name")
                         })?),
-                        val: *ass.right,
+                        val: locate_at_name(*ass.right, ass.left.span()),
                     },
                     expr => Arg {
-                        typ: ArgType::Unnamed(None),
+                        typ: ArgType::Unnamed(pun_name(&expr)),
                         val: expr,
                     },
                 };
                 out.push(arg);
             }
+            RawArg::Default {
+                name,
+                _question_token: _,
+                _eq_token: _,
+                val,
+            } => {
+                out.push(Arg {
+                    typ: ArgType::Default(name.to_string()),
+                    val: locate_at_name(val, name.span()),
+                });
+            }
+            RawArg::Fields {
+                _splat_token: _,
+                parent,
+            } => {
+                out.push(Arg {
+                    typ: ArgType::Fields,
+                    val: parent,
+                });
+            }
+            RawArg::Tuple {
+                _splat_token: _,
+                parent,
+                names,
+            } => {
+                // the parent is evaluated once, since it's often a call
+                let local = format_ident!(
+                    "__sqlx_named_tuple_{}",
+                    bindings.len(),
+                    span = Span::mixed_site(),
+                );
+                bindings.push(quote! { let #local = &(#parent); });
+                extract_tuple(local.into_token_stream(), &names, out)?;
+            }
             RawArg::Splat {
                 _splat_token: _,
                 parent,
+                prefix,
+                optional,
                 _brace_token: _,
                 children,
             } => {
-                for pair in children.into_pairs() {
-                    let (child, comma) = pair.into_tuple();
-
-                    let RawChild {
-                        assign,
-                        dot_token,
-                        target,
-                        cast,
-                    } = child;
-
-                    let typ = match (assign, target.first()) {
-                        (Some(ass), _) => ArgType::Named(ass.name.to_string()),
-                        (_, Some(RawChildTarget::Member(Member::Named(name))))
-                            if target.len() == 1 =>
-                        {
-                            ArgType::Unnamed(Some(name.to_string()))
-                        }
-                        _ => ArgType::Unnamed(None),
-                    };
+                let prefix = prefix.map(|prefix| prefix.to_string()).unwrap_or_default();
+                extract_children(
+                    parent.into_token_stream(),
+                    &prefix,
+                    optional,
+                    children,
+                    out,
+                    bindings,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `parent.target`, or `parent.as_ref().map(|p| &p.target)` when the parent is optional
+fn child_access(
+    parent: &TokenStream,
+    optional: bool,
+    dot_token: &Token![.],
+    target: &Punctuated<RawChildTarget, Token![.]>,
+) -> TokenStream {
+    if !optional {
+        return quote! { #parent #dot_token #target };
+    }
+    let local = Ident::new("__sqlx_named_parent", Span::mixed_site());
+    // fields are borrowed, while methods already return owned values
+    let borrow = matches!(target.last(), Some(RawChildTarget::Member(_))).then(|| quote! { & });
+    quote! { #parent.as_ref().map(|#local| #borrow #local #dot_token #target) }
+}
 
-                    let arg = Arg {
-                        typ,
-                        val: parse_quote! { #parent #dot_token #target #cast },
-                    };
+fn extract_children(
+    parent: TokenStream,
+    prefix: &str,
+    optional: bool,
+    children: Punctuated<RawChild, Token![,]>,
+    out: &mut Punctuated<Arg, Token![,]>,
+    bindings: &mut Vec<TokenStream>,
+) -> syn::Result<()> {
+    // the parent is evaluated once, before the query, for all of its children
+    let local = format_ident!(
+        "__sqlx_named_parent_{}",
+        bindings.len(),
+        span = Span::mixed_site().located_at(parent.span()),
+    );
+    bindings.push(quote! { let #local = &(#parent); });
+    let parent = quote! { #local };
 
-                    out.push(arg);
+    for pair in children.into_pairs() {
+        let (child, comma) = pair.into_tuple();
 
-                    if let Some(comma) = comma {
-                        out.push_punct(comma);
+        let RawAccess {
+            assign,
+            dot_token,
+            target,
+            cast,
+            nested,
+        } = match child {
+            RawChild::Access(access) => access,
+            RawChild::Closure { assign, closure } => {
+                if closure.inputs.len() != 1 {
+                    return Err(syn::Error::new_spanned(
+                        &closure.inputs,
+                        "the closure takes the parent as its only argument",
+                    ));
+                }
+                // passing the closure where its argument's type is known lets it be inferred
+                let val = if optional {
+                    parse_quote! { #local.as_ref().map(#closure) }
+                } else {
+                    parse_quote! {
+                        {
+                            fn __sqlx_named_apply<'a, T: ?Sized, R>(
+                                parent: &'a T,
+                                f: impl ::core::ops::FnOnce(&'a T) -> R,
+                            ) -> R {
+                                f(parent)
+                            }
+                            __sqlx_named_apply(#local, #closure)
+                        }
                     }
+                };
+                out.push(Arg {
+                    typ: ArgType::Named(format!("{}{}", prefix, assign.name)),
+                    val: locate_at_name(val, assign.name.span()),
+                });
+                if let Some(comma) = comma {
+                    out.push_punct(comma);
                 }
+                continue;
             }
+        };
+
+        if let Some(nested) = nested {
+            if let Some(ass) = assign {
+                return Err(syn::Error::new_spanned(
+                    ass.name,
+                    "nested splats can't be named",
+                ));
+            }
+            extract_children(
+                child_access(&parent, optional, &dot_token, &target),
+                prefix,
+                optional,
+                nested.children,
+                out,
+                bindings,
+            )?;
+        } else {
+            let (typ, name_span) = match (assign, target.first()) {
+                (Some(ass), _) => (
+                    ArgType::Named(format!("{}{}", prefix, ass.name)),
+                    ass.name.span(),
+                ),
+                (_, Some(RawChildTarget::Member(Member::Named(name)))) if target.len() == 1 => (
+                    ArgType::Unnamed(Some(format!("{}{}", prefix, name))),
+                    name.span(),
+                ),
+                _ => (ArgType::Unnamed(None), dot_token.span),
+            };
+
+            let access = child_access(&parent, optional, &dot_token, &target);
+            out.push(Arg {
+                typ,
+                val: locate_at_name(parse_quote! { #access #cast }, name_span),
+            });
         }
 
-        Ok(())
+        if let Some(comma) = comma {
+            if !out.empty_or_trailing() {
+                out.push_punct(comma);
+            }
+        }
     }
+
+    Ok(())
+}
+
+fn extract_tuple(
+    parent: TokenStream,
+    names: &TypeTuple,
+    out: &mut Punctuated<Arg, Token![,]>,
+) -> syn::Result<()> {
+    for (index, elem) in names.elems.iter().enumerate() {
+        let index = Index::from(index);
+        let val = quote! { #parent.#index };
+        match elem {
+            Type::Infer(_) => {}
+            Type::Tuple(tuple) => extract_tuple(val, tuple, out)?,
+            Type::Path(path) if path.qself.is_none() && path.path.get_ident().is_some() => {
+                let name = path.path.get_ident().unwrap();
+                out.push(Arg {
+                    typ: ArgType::Unnamed(Some(name.to_string())),
+                    val: locate_at_name(parse_quote! { #val }, name.span()),
+                });
+            }
+            elem => {
+                return Err(syn::Error::new_spanned(
+                    elem,
+                    "expected a name, `_` or a nested tuple",
+                ))
+            }
+        }
+    }
+    Ok(())
 }
 
 impl Parse for RawArg {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         Ok(if input.peek(Token![..]) {
-            let content;
-            RawArg::Splat {
-                _splat_token: input.parse()?,
-                parent: Expr::parse_without_eager_brace(input)?,
-                _brace_token: braced!(content in input),
-                children: content.parse_terminated(RawChild::parse, Token![,])?,
+            let splat_token = input.parse()?;
+            let parent = Expr::parse_without_eager_brace(input)?;
+            if input.peek(Brace) {
+                let content;
+                // the prefix is parsed as a cast to a type named like it
+                let (parent, prefix) = match parent {
+                    Expr::Cast(cast) => match &*cast.ty {
+                        Type::Path(path) if path.qself.is_none() => match path.path.get_ident() {
+                            Some(prefix) => (*cast.expr, Some(prefix.clone())),
+                            None => (Expr::Cast(cast), None),
+                        },
+                        _ => (Expr::Cast(cast), None),
+                    },
+                    parent => (parent, None),
+                };
+                let (parent, optional) = match parent {
+                    Expr::Try(tr) => (*tr.expr, true),
+                    parent => (parent, false),
+                };
+                RawArg::Splat {
+                    _splat_token: splat_token,
+                    parent,
+                    prefix,
+                    optional,
+                    _brace_token: braced!(content in input),
+                    children: content.parse_terminated(RawChild::parse, Token![,])?,
+                }
+            } else {
+                match parent {
+                    Expr::Cast(cast) if matches!(*cast.ty, Type::Tuple(_)) => {
+                        let Type::Tuple(names) = *cast.ty else {
+                            unreachable!()
+                        };
+                        RawArg::Tuple {
+                            _splat_token: splat_token,
+                            parent: *cast.expr,
+                            names,
+                        }
+                    }
+                    parent => RawArg::Fields {
+                        _splat_token: splat_token,
+                        parent,
+                    },
+                }
+            }
+        } else if input.peek(Ident) && input.peek2(Token![?]) && input.peek3(Token![=]) {
+            RawArg::Default {
+                name: input.parse()?,
+                _question_token: input.parse()?,
+                _eq_token: input.parse()?,
+                val: input.parse()?,
             }
         } else {
             RawArg::Single(input.parse()?)
@@ -187,28 +556,93 @@ impl Parse for RawArg {
     }
 }
 
-struct RawChild {
+enum RawChild {
+    Access(RawAccess),
+    /// `name = |parent| expr`, which can be a `move` closure or declare its return type
+    Closure {
+        assign: Assign,
+        closure: ExprClosure,
+    },
+}
+
+impl Parse for RawChild {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(
+            if input.peek(Ident)
+                && input.peek2(Token![=])
+                && (input.peek3(Token![|]) || input.peek3(Token![move]))
+            {
+                RawChild::Closure {
+                    assign: input.parse()?,
+                    closure: input.parse()?,
+                }
+            } else {
+                RawChild::Access(input.parse()?)
+            },
+        )
+    }
+}
+
+struct RawAccess {
     assign: Option<Assign>,
     dot_token: Token![.],
     target: Punctuated<RawChildTarget, Token![.]>,
     cast: Option<Cast>,
+    nested: Option<Nested>,
 }
 
-impl Parse for RawChild {
+impl Parse for RawAccess {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let assign = if input.peek(Ident) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let dot_token = input.parse()?;
+
+        // `.` also peeks the start of a nested `..`
+        let mut target = Punctuated::new();
+        target.push_value(input.parse()?);
+        while input.peek(Token![.]) && !input.peek(Token![..]) {
+            target.push_punct(input.parse()?);
+            target.push_value(input.parse()?);
+        }
+
+        let nested = if input.peek(Token![..]) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let cast = if nested.is_none() && input.peek(Token![as]) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
         Ok(Self {
-            assign: if input.peek(Ident) {
-                Some(input.parse()?)
-            } else {
-                None
-            },
-            dot_token: input.parse()?,
-            target: Punctuated::parse_separated_nonempty(input)?,
-            cast: if input.peek(Token![as]) {
-                Some(input.parse()?)
-            } else {
-                None
-            },
+            assign,
+            dot_token,
+            target,
+            cast,
+            nested,
+        })
+    }
+}
+
+/// `..{ .child, ... }` after a child, splatting its result
+struct Nested {
+    _splat_token: Token![..],
+    _brace_token: Brace,
+    children: Punctuated<RawChild, Token![,]>,
+}
+
+impl Parse for Nested {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        Ok(Self {
+            _splat_token: input.parse()?,
+            _brace_token: braced!(content in input),
+            children: content.parse_terminated(RawChild::parse, Token![,])?,
         })
     }
 }
@@ -0,0 +1,331 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote, Attribute, Expr, FnArg, GenericArgument, Ident, ItemTrait, LitStr, Pat,
+    PathArguments, ReturnType, Token, TraitItem, Type,
+};
+
+use sqlx_named_core::args::get_name;
+
+use crate::{
+    expand,
+    header::{parse_header, Header},
+    parse::{Arg, ArgType, QueryInput},
+    util::read_file_src,
+};
+
+pub struct RepositoryArgs {
+    executor: Type,
+}
+
+impl Parse for RepositoryArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            executor: if input.is_empty() {
+                parse_quote! { ::sqlx::PgPool }
+            } else {
+                input.parse()?
+            },
+        })
+    }
+}
+
+struct SqlAttr {
+    sql: String,
+    sql_span: Span,
+    /// The header of a `#[sql_file]`, which is read like `query_file!` reads it
+    header: Header,
+    scalar: bool,
+}
+
+impl SqlAttr {
+    fn take(attrs: &mut Vec<Attribute>) -> syn::Result<Option<Self>> {
+        let Some(pos) = attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("sql") || attr.path().is_ident("sql_file"))
+        else {
+            return Ok(None);
+        };
+        let attr = attrs.remove(pos);
+        let file = attr.path().is_ident("sql_file");
+
+        attr.parse_args_with(|input: ParseStream| {
+            let lit_str = input.parse::<LitStr>()?;
+            let (sql, header) = if file {
+                let sql = read_file_src(&lit_str.value(), lit_str.span())?;
+                let header = parse_header(&sql, lit_str.span())?;
+                (sql, header)
+            } else {
+                (lit_str.value(), Header::default())
+            };
+
+            let mut scalar = false;
+            while !input.is_empty() {
+                input.parse::<Token![,]>()?;
+                if input.is_empty() {
+                    break;
+                }
+                let flag = input.parse::<Ident>()?;
+                match flag.to_string().as_str() {
+                    "scalar" => scalar = true,
+                    _ => return Err(syn::Error::new_spanned(flag, "unknown flag")),
+                }
+            }
+
+            Ok(Some(Self {
+                sql,
+                sql_span: lit_str.span(),
+                header,
+                scalar,
+            }))
+        })
+    }
+}
+
+enum Fetch<'a> {
+    Execute,
+    One(&'a Type),
+    Optional(&'a Type),
+    All(&'a Type),
+}
+
+fn single_generic<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+impl<'a> Fetch<'a> {
+    fn from_output(output: &'a ReturnType) -> syn::Result<Self> {
+        let ok_type = match output {
+            ReturnType::Type(_, ty) => single_generic(ty, "Result"),
+            ReturnType::Default => None,
+        }
+        .ok_or_else(|| {
+            syn::Error::new_spanned(output, "repository methods must return a Result")
+        })?;
+
+        Ok(match ok_type {
+            Type::Tuple(tuple) if tuple.elems.is_empty() => Fetch::Execute,
+            ty => {
+                if let Some(row) = single_generic(ty, "Vec") {
+                    Fetch::All(row)
+                } else if let Some(row) = single_generic(ty, "Option") {
+                    Fetch::Optional(row)
+                } else {
+                    Fetch::One(ty)
+                }
+            }
+        })
+    }
+}
+
+pub fn expand_repository(args: RepositoryArgs, mut item: ItemTrait) -> syn::Result<TokenStream> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "generic repository traits are not supported",
+        ));
+    }
+
+    let mut impl_fns = Vec::new();
+    let mut mock_fields = Vec::new();
+    let mut mock_setters = Vec::new();
+    let mut mock_fns = Vec::new();
+
+    let mock_ident = format_ident!("Mock{}", item.ident);
+
+    for trait_item in &mut item.items {
+        let TraitItem::Fn(method) = trait_item else {
+            continue;
+        };
+
+        let Some(sql_attr) = SqlAttr::take(&mut method.attrs)? else {
+            if method.default.is_none() {
+                return Err(syn::Error::new_spanned(
+                    &method.sig,
+                    "repository methods without a default body need a #[sql] or #[sql_file] attribute",
+                ));
+            }
+            continue;
+        };
+
+        let sig = &method.sig;
+        if sig.asyncness.is_none() {
+            return Err(syn::Error::new_spanned(
+                sig,
+                "repository methods must be async",
+            ));
+        }
+        if !sig.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &sig.generics,
+                "generic repository methods are not supported",
+            ));
+        }
+
+        let mut inputs = sig.inputs.iter();
+        match inputs.next() {
+            Some(FnArg::Receiver(receiver))
+                if receiver.reference.is_some() && receiver.mutability.is_none() => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    sig,
+                    "repository methods must take `&self`",
+                ))
+            }
+        }
+
+        let mut arg_idents = Vec::new();
+        let mut arg_types = Vec::new();
+        for input in inputs {
+            let FnArg::Typed(pat_type) = input else {
+                return Err(syn::Error::new_spanned(input, "unexpected receiver"));
+            };
+            let Pat::Ident(pat_ident) = &*pat_type.pat else {
+                return Err(syn::Error::new_spanned(
+                    &pat_type.pat,
+                    "repository method arguments must be plain identifiers",
+                ));
+            };
+            arg_idents.push(pat_ident.ident.clone());
+            arg_types.push(&*pat_type.ty);
+        }
+
+        let fetch = Fetch::from_output(&sig.output)?;
+
+        let (out_ident, as_type) = match &fetch {
+            Fetch::Execute => ("query", None),
+            Fetch::One(row) | Fetch::Optional(row) | Fetch::All(row) => {
+                if sql_attr.scalar {
+                    ("query_scalar", None)
+                } else {
+                    ("query_as", Some((*row).clone()))
+                }
+            }
+        };
+
+        let out_ident = Ident::new(out_ident, Span::call_site());
+
+        let query_args = arg_idents
+            .iter()
+            .map(|ident| {
+                let val: Expr = parse_quote! { #ident };
+                Arg {
+                    typ: ArgType::Unnamed(get_name(&val)),
+                    val,
+                }
+            })
+            .chain(sql_attr.header.defaults.into_iter().map(|(name, val)| Arg {
+                typ: ArgType::Default(name),
+                val,
+            }))
+            .collect();
+
+        let query = expand(
+            QueryInput {
+                as_type,
+                sql: sql_attr.sql,
+                sql_span: sql_attr.sql_span,
+                args: query_args,
+                param_types: sql_attr.header.param_types,
+                bindings: Vec::new(),
+                unused_args: None,
+                bind_each: false,
+                function: Some(sig.ident.to_string()),
+            },
+            &out_ident.to_string(),
+            parse_quote! { ::sqlx::#out_ident },
+            false,
+        )?;
+
+        let body = match fetch {
+            Fetch::Execute => quote! {
+                #query.execute(self).await?;
+                ::core::result::Result::Ok(())
+            },
+            Fetch::One(_) => quote! {
+                ::core::result::Result::Ok(#query.fetch_one(self).await?)
+            },
+            Fetch::Optional(_) => quote! {
+                ::core::result::Result::Ok(#query.fetch_optional(self).await?)
+            },
+            Fetch::All(_) => quote! {
+                ::core::result::Result::Ok(#query.fetch_all(self).await?)
+            },
+        };
+
+        impl_fns.push(quote! {
+            #sig {
+                #body
+            }
+        });
+
+        let ident = &sig.ident;
+        let ret = match &sig.output {
+            ReturnType::Type(_, ty) => ty,
+            ReturnType::Default => unreachable!(),
+        };
+        let handler = quote! { dyn Fn(#(#arg_types),*) -> #ret + Send + Sync };
+        let setter = format_ident!("on_{}", ident);
+        let missing = format!("{}::{} called without a handler", mock_ident, ident);
+
+        mock_fields.push(quote! {
+            #ident: ::core::option::Option<::std::boxed::Box<#handler>>
+        });
+        mock_setters.push(quote! {
+            pub fn #setter(
+                mut self,
+                handler: impl Fn(#(#arg_types),*) -> #ret + Send + Sync + 'static,
+            ) -> Self {
+                self.#ident = ::core::option::Option::Some(::std::boxed::Box::new(handler));
+                self
+            }
+        });
+        mock_fns.push(quote! {
+            #sig {
+                (self.#ident.as_ref().expect(#missing))(#(#arg_idents),*)
+            }
+        });
+    }
+
+    let trait_ident = &item.ident;
+    let vis = &item.vis;
+    let executor = &args.executor;
+
+    Ok(quote! {
+        #item
+
+        impl #trait_ident for #executor {
+            #(#impl_fns)*
+        }
+
+        #[cfg(test)]
+        #[derive(Default)]
+        #vis struct #mock_ident {
+            #(#mock_fields,)*
+        }
+
+        #[cfg(test)]
+        impl #mock_ident {
+            #(#mock_setters)*
+        }
+
+        #[cfg(test)]
+        impl #trait_ident for #mock_ident {
+            #(#mock_fns)*
+        }
+    })
+}
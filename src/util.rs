@@ -1,6 +1,9 @@
 use std::path::{Path, PathBuf};
 
-use proc_macro2::Span;
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use syn::{parse::Parse, token, Expr, ExprParen};
+
+use crate::config;
 
 // from sqlx-macros-core
 pub fn read_file_src(source: &str, source_span: Span) -> syn::Result<String> {
@@ -29,12 +32,15 @@ fn resolve_path(path: impl AsRef<Path>, err_span: Span) -> syn::Result<PathBuf>
         ));
     }
 
+    let config = config::load(err_span)?;
+
     // requires `proc_macro::SourceFile::path()` to be stable
     // https://github.com/rust-lang/rust/issues/54725
-    if path.is_relative()
-        && !path
+    if config.sql_root.is_none()
+        && path.is_relative()
+        && path
             .parent()
-            .map_or(false, |parent| !parent.as_os_str().is_empty())
+            .is_none_or(|parent| parent.as_os_str().is_empty())
     {
         return Err(syn::Error::new(
             err_span,
@@ -48,7 +54,41 @@ fn resolve_path(path: impl AsRef<Path>, err_span: Span) -> syn::Result<PathBuf>
             "CARGO_MANIFEST_DIR is not set; please use Cargo to build",
         )
     })?;
-    let base_dir_path = Path::new(&base_dir);
+    Ok(config.query_path(Path::new(&base_dir), path))
+}
+
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            if let TokenTree::Group(group) = &token {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                TokenTree::Group(respanned)
+            } else {
+                token.set_span(span);
+                token
+            }
+        })
+        .collect()
+}
 
-    Ok(base_dir_path.join(path))
+/// Parses code written in a string, locating it at `span` so that errors about it point there
+/// instead of at the whole macro call
+pub fn parse_at<T: Parse>(src: &str, span: Span) -> syn::Result<T> {
+    let tokens = src.parse::<TokenStream>()?;
+    syn::parse2(respan(tokens, span))
+}
+
+/// Wraps an arg in parentheses located at its name, where `sqlx` then reports type mismatches of
+/// the arg. Type overrides are left as is, since `sqlx` doesn't check their args
+pub fn locate_at_name(val: Expr, name: Span) -> Expr {
+    if matches!(val, Expr::Cast(_)) {
+        return val;
+    }
+    Expr::Paren(ExprParen {
+        attrs: Vec::new(),
+        paren_token: token::Paren(name),
+        expr: Box::new(val),
+    })
 }
@@ -669,3 +669,754 @@ async fn test_query_named_splat(db: PgPool) -> Result<()> {
 
     Ok(())
 }
+
+#[sqlx::test]
+async fn test_query_prefixed_splat(db: PgPool) -> Result<()> {
+    let old = Args {
+        id: 1,
+        money: MyInt4(2),
+    };
+    let new = Args {
+        id: 3,
+        money: MyInt4(4),
+    };
+    let record = sqlx_named::query!(
+        r#"select $old_id::int "old_id!", $old_money::int "old_money!", $new_id::int "new_id!", $new_money::int "new_money!" "#,
+        ..old as old_ {.id, .money as _},
+        ..new as new_ {.id, money = .money as _},
+    )
+    .fetch_one(&db)
+    .await?;
+
+    assert_eq!(record.old_id, 1);
+    assert_eq!(record.old_money, 2);
+    assert_eq!(record.new_id, 3);
+    assert_eq!(record.new_money, 4);
+
+    Ok(())
+}
+
+struct Address {
+    street: String,
+    number: i32,
+}
+
+impl Address {
+    fn city(&self) -> String {
+        "Springfield".to_owned()
+    }
+}
+
+#[sqlx::test]
+async fn test_query_optional_splat(db: PgPool) -> Result<()> {
+    let address = Some(Address {
+        street: "Evergreen Terrace".to_owned(),
+        number: 742,
+    });
+    let record = sqlx_named::query!(
+        r#"select $street::text "street", $number::int "number", $city::text "city" "#,
+        ..address? {.street, .number, city = .city()},
+    )
+    .fetch_one(&db)
+    .await?;
+
+    assert_eq!(record.street.as_deref(), Some("Evergreen Terrace"));
+    assert_eq!(record.number, Some(742));
+    assert_eq!(record.city.as_deref(), Some("Springfield"));
+
+    let address: Option<Address> = None;
+    let record = sqlx_named::query!(
+        r#"select $street::text "street", $number::int "number", $city::text "city" "#,
+        ..address? {.street, .number, city = .city()},
+    )
+    .fetch_one(&db)
+    .await?;
+
+    assert_eq!(record.street, None);
+    assert_eq!(record.number, None);
+    assert_eq!(record.city, None);
+
+    // in parentheses, `?` propagates the error instead
+    let address: Result<Address> = Ok(Address {
+        street: "Evergreen Terrace".to_owned(),
+        number: 742,
+    });
+    let record = sqlx_named::query!(
+        r#"select $street::text "street", $number::int "number" "#,
+        ..(address?) {.street, .number},
+    )
+    .fetch_one(&db)
+    .await?;
+
+    assert_eq!(record.street.as_deref(), Some("Evergreen Terrace"));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_closure_splat(db: PgPool) -> Result<()> {
+    let address = Address {
+        street: "Evergreen Terrace".to_owned(),
+        number: 742,
+    };
+    let record = sqlx_named::query!(
+        r#"select $line::text "line!", $next::int "next!" "#,
+        ..address {line = |a| format!("{} {}", a.number, a.street), next = |a| a.number + 1},
+    )
+    .fetch_one(&db)
+    .await?;
+
+    assert_eq!(record.line, "742 Evergreen Terrace");
+    assert_eq!(record.next, 743);
+
+    let address = Some(address);
+    let record = sqlx_named::query!(
+        r#"select $line::text "line" "#,
+        ..address? {line = |a| format!("{} {}", a.number, a.street)},
+    )
+    .fetch_one(&db)
+    .await?;
+
+    assert_eq!(record.line.as_deref(), Some("742 Evergreen Terrace"));
+
+    let mut evaluations = 0;
+    let mut lookup = || {
+        evaluations += 1;
+        Address {
+            street: "Evergreen Terrace".to_owned(),
+            number: 742,
+        }
+    };
+    let suffix = String::from(", Springfield");
+    let record = sqlx_named::query!(
+        r#"select $number::int "number!", $line::text "line!" "#,
+        ..lookup() {.number, line = move |a| -> String { format!("{}{}", a.street, suffix) }},
+    )
+    .fetch_one(&db)
+    .await?;
+
+    assert_eq!(record.number, 742);
+    assert_eq!(record.line, "Evergreen Terrace, Springfield");
+    assert_eq!(evaluations, 1);
+
+    Ok(())
+}
+
+struct Pair(i32, String);
+
+fn split_pair() -> (i32, (String, bool)) {
+    (1, ("one".to_owned(), true))
+}
+
+#[sqlx::test]
+async fn test_query_tuple_splat(db: PgPool) -> Result<()> {
+    let record = sqlx_named::query!(
+        r#"select $id::int "id!", $name::text "name!" "#,
+        ..split_pair() as (id, (name, _)),
+    )
+    .fetch_one(&db)
+    .await?;
+
+    assert_eq!(record.id, 1);
+    assert_eq!(record.name, "one");
+
+    let pair = Pair(2, "two".to_owned());
+    let record = sqlx_named::query!(
+        r#"select $1::int "id!", $2::text "name!" "#,
+        ..pair as (id, name)
+    )
+    .fetch_one(&db)
+    .await?;
+
+    assert_eq!(record.id, 2);
+    assert_eq!(record.name, "two");
+
+    Ok(())
+}
+
+struct Request {
+    args: Args,
+    page: i32,
+}
+
+#[sqlx::test]
+async fn test_query_nested_splat(db: PgPool) -> Result<()> {
+    let request = Request {
+        args: Args {
+            id: 2,
+            money: MyInt4(3),
+        },
+        page: 1,
+    };
+    let record = sqlx_named::query!(
+        r#"select $id::int "id!", $money::int "money!", $age::int "age!", $page::int "page!" "#,
+        ..request{.args..{.id, .money as _, age = .age()}, .page},
+    )
+    .fetch_one(&db)
+    .await?;
+
+    assert_eq!(record.id, 2);
+    assert_eq!(record.money, 3);
+    assert_eq!(record.age, 4);
+    assert_eq!(record.page, 1);
+
+    Ok(())
+}
+
+#[sqlx_named::repository]
+trait AccountRepo {
+    #[sql(
+        r#"SELECT id "id!", name from (VALUES (1, 'Herp Derpinson'), (2, null)) accounts(id, name) where id = $id"#
+    )]
+    async fn get(&self, id: i32) -> Result<Account>;
+
+    #[sql(
+        r#"SELECT id "id!", name from (VALUES (1, 'Herp Derpinson'), (2, null)) accounts(id, name) where id = $id"#
+    )]
+    async fn find(&self, id: i32) -> Result<Option<Account>>;
+
+    #[sql(
+        r#"SELECT id "id!", name from (VALUES (1, 'Herp Derpinson'), (2, null)) accounts(id, name) order by id"#
+    )]
+    async fn list(&self) -> Result<Vec<Account>>;
+
+    #[sql(
+        r#"SELECT count(*) "count!" from (VALUES (1, 'Herp Derpinson'), (2, null)) accounts(id, name)"#,
+        scalar
+    )]
+    async fn count(&self) -> Result<i64>;
+
+    #[sql_file("./tests/test-query-named.sql")]
+    async fn get_from_file(&self, id: i32) -> Result<Account>;
+
+    #[sql_file("./tests/test-query-default.sql")]
+    async fn get_default(&self) -> Result<Account>;
+
+    #[sql_file("./tests/test-query-param-types.sql")]
+    async fn get_typed(&self, id: MyInt4) -> Result<Account>;
+
+    #[sql("select pg_sleep($seconds)")]
+    async fn sleep(&self, seconds: f64) -> Result<()>;
+
+    #[sql("select pg_sleep(0) where true [[ and $flag::bool ]]")]
+    async fn touch(&self, flag: Option<bool>) -> Result<()>;
+}
+
+#[sqlx::test]
+async fn test_repository(db: PgPool) -> Result<()> {
+    let account = db.get(1).await?;
+    assert_eq!(account.id, 1);
+    assert_eq!(account.name.as_deref(), Some("Herp Derpinson"));
+
+    assert!(db.find(3).await?.is_none());
+
+    let accounts = db.list().await?;
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[1].id, 2);
+    assert_eq!(accounts[1].name, None);
+
+    assert_eq!(db.count().await?, 2);
+
+    let account = db.get_from_file(1).await?;
+    assert_eq!(account.id, 1);
+
+    assert_eq!(db.get_default().await?.id, 1);
+    assert_eq!(db.get_typed(MyInt4(1)).await?.id, 1);
+
+    db.sleep(0.0).await?;
+    db.touch(None).await?;
+    db.touch(Some(false)).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_repository_mock() -> Result<()> {
+    let repo = MockAccountRepo::default()
+        .on_get(|id| Ok(Account { id, name: None }))
+        .on_count(|| Ok(7));
+
+    assert_eq!(repo.get(3).await?.id, 3);
+    assert_eq!(repo.count().await?, 7);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_optional_predicate(db: PgPool) -> Result<()> {
+    let name: Option<&str> = None;
+    let rows = sqlx_named::query!(
+        "SELECT id from (VALUES (1, 'Herp Derpinson'), (2, 'Derp Herpinson')) accounts(id, name) where accounts.name = $name? order by id",
+        name,
+    )
+    .fetch_all(&db)
+    .await?;
+    assert_eq!(rows.len(), 2);
+
+    let name = Some("Derp Herpinson");
+    let rows = sqlx_named::query!(
+        "SELECT id from (VALUES (1, 'Herp Derpinson'), (2, 'Derp Herpinson')) accounts(id, name) where accounts.name = $name? order by id",
+        name,
+    )
+    .fetch_all(&db)
+    .await?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].id, Some(2));
+
+    let rows = sqlx_named::query!(
+        "SELECT id from (VALUES (1, 'Herp Derpinson'), (2, 'Derp Herpinson')) accounts(id, name) where id >= $id?::int4 and name like $name? order by id",
+        id = Some(MyInt4(2)) as Option<MyInt4>,
+        name = None as Option<&str>,
+    )
+    .fetch_all(&db)
+    .await?;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].id, Some(2));
+
+    // the jsonb `?` operator is left alone, even right before a string
+    let has = sqlx_named::query_scalar!(
+        r#"SELECT '{"k": 1}'::jsonb ?'k' "has!" where 1 = $id"#,
+        id = 1,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert!(has);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_conditional_blocks(db: PgPool) -> Result<()> {
+    for (min_id, name, expected) in [
+        (None, None, vec![1, 2, 3]),
+        (Some(2), None, vec![2, 3]),
+        (Some(2), Some("Herp"), vec![3]),
+        (None, Some("Herp"), vec![1, 3]),
+    ] {
+        let ids = sqlx_named::query_scalar!(
+            r#"SELECT id "id!" from (VALUES (1, 'Herp'), (2, 'Derp'), (3, 'Herp')) accounts(id, name) where true [[ and id >= $min_id ]] [[ and name = $name ]] order by id"#,
+            min_id as Option<i32>,
+            name,
+        )
+        .fetch_all(&db)
+        .await?;
+
+        assert_eq!(ids, expected);
+    }
+
+    let account = sqlx_named::query_as!(
+        Account,
+        r#"SELECT id "id!", name from (VALUES (1, 'Herp Derpinson'), (2, null)) accounts(id, name) where array[[id]] = array[[1]] [[ and name = $name ]]"#,
+        name = Some("Herp Derpinson"),
+    )
+    .fetch_optional(&db)
+    .await?;
+    assert_eq!(account.map(|account| account.id), Some(1));
+
+    let flag = Some(true);
+    sqlx_named::query!("select pg_sleep(0) where true [[ and $flag::bool ]]", flag)
+        .execute(&db)
+        .await?;
+
+    Ok(())
+}
+
+enum Direction {
+    Asc,
+    Desc,
+}
+
+impl AsRef<str> for Direction {
+    fn as_ref(&self) -> &str {
+        match self {
+            Direction::Asc => "asc",
+            Direction::Desc => "desc",
+        }
+    }
+}
+
+#[sqlx::test]
+async fn test_query_identifier_choice(db: PgPool) -> Result<()> {
+    for (sort, dir, expected) in [
+        ("id", Direction::Asc, vec![1, 2, 3]),
+        ("id", Direction::Desc, vec![3, 2, 1]),
+        ("name", Direction::Asc, vec![2, 1, 3]),
+    ] {
+        let ids = sqlx_named::query_scalar!(
+            r#"SELECT id "id!" from (VALUES (1, 'b'), (2, 'a'), (3, 'c')) accounts(id, name) order by ${sort in [id, name]} ${dir in [asc, desc]}"#,
+            sort,
+            dir,
+        )
+        .fetch_all(&db)
+        .await?;
+
+        assert_eq!(ids, expected);
+    }
+
+    let sort = String::from("name");
+    let ids = sqlx_named::query_scalar!(
+        r#"SELECT id "id!" from (VALUES (1, 'b'), (2, 'a'), (3, 'c')) accounts(id, name) where true [[ and id > $min_id ]] order by ${sort in [id, name]}"#,
+        sort,
+        min_id = Some(1),
+    )
+    .fetch_all(&db)
+    .await?;
+    assert_eq!(ids, vec![2, 3]);
+
+    // a choice used twice makes the same decision both times
+    let ids = sqlx_named::query_scalar!(
+        r#"SELECT id "id!" from (VALUES (1, 'b'), (2, 'a'), (3, 'c')) accounts(id, name) where ${sort in [id, name]} is not null order by ${sort in [id, name]}"#,
+        sort,
+    )
+    .fetch_all(&db)
+    .await?;
+    assert_eq!(ids, vec![2, 1, 3]);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_identifier_choice_invalid(db: PgPool) -> Result<()> {
+    let sort = "name; drop table accounts";
+    let err = sqlx_named::query_scalar!(
+        r#"SELECT id "id!" from (VALUES (1, 'b'), (2, 'a'), (3, 'c')) accounts(id, name) order by ${sort in [id, name]}"#,
+        sort,
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap_err();
+    assert!(matches!(err, sqlx::Error::Encode(_)));
+    assert!(err.to_string().contains(
+        "`name; drop table accounts` is not allowed for `sort`, expected one of: id, name"
+    ));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_default_args(db: PgPool) -> Result<()> {
+    let ids = sqlx_named::query_scalar!(
+        r#"SELECT id "id!" from unnest(array[1, 2, 3]) ids(id) limit $limit"#,
+        limit? = 2i64,
+    )
+    .fetch_all(&db)
+    .await?;
+    assert_eq!(ids, vec![1, 2]);
+
+    let limit = 1i64;
+    let ids = sqlx_named::query_scalar!(
+        r#"SELECT id "id!" from unnest(array[1, 2, 3]) ids(id) limit $limit"#,
+        limit,
+        limit? = 2i64,
+        offset? = 0i64,
+    )
+    .fetch_all(&db)
+    .await?;
+    assert_eq!(ids, vec![1]);
+
+    let account = sqlx_named::query_file!("./tests/test-query-default.sql")
+        .fetch_one(&db)
+        .await?;
+    assert_eq!(account.id, 1);
+
+    let account = sqlx_named::query_file!("./tests/test-query-default.sql", id = 2i32)
+        .fetch_optional(&db)
+        .await?;
+    assert!(account.is_none());
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_allow_unused_args(db: PgPool) -> Result<()> {
+    let account = sqlx_named::query_as!(
+        #[allow(unused_args)]
+        Account,
+        r#"SELECT id "id!", name from (VALUES (1, 'Herp Derpinson')) accounts(id, name) where id = $id"#,
+        id = 1,
+        page = 2,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(account.id, 1);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_file_param_types(db: PgPool) -> Result<()> {
+    let account = sqlx_named::query_file!("./tests/test-query-param-types.sql", id = MyInt4(1))
+        .fetch_one(&db)
+        .await?;
+    assert_eq!(account.id, 1);
+
+    let id = MyInt4(1);
+    let account = sqlx_named::query_file!("./tests/test-query-param-types.sql", id as MyInt4)
+        .fetch_one(&db)
+        .await?;
+    assert_eq!(account.id, 1);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_type_hints(db: PgPool) -> Result<()> {
+    let record = sqlx_named::query!(
+        "select * from (select 1::int4) records(id) where id = $id: MyInt4",
+        id = MyInt4(1),
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(record.id, Some(1i32));
+
+    let id = Some(MyInt4(1));
+    let record = sqlx_named::query!(
+        "select * from (select 1::int4) records(id) where records.id = $id: Option<MyInt4>?",
+        id,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(record.id, Some(1i32));
+
+    let record = sqlx_named::query!(
+        "select * from (select 1::int4) records(id) where id = $1: MyInt4",
+        MyInt4(1),
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(record.id, Some(1i32));
+
+    let record = sqlx_named::query!(
+        r#"select (array[1, 2, 3])[$lower:$upper] "ids!""#,
+        lower = 2,
+        upper = 3,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(record.ids, vec![2, 3]);
+
+    let record = sqlx_named::query!(
+        r#"select (array[1, 2, 3])[$lower:upper] "ids!" from (select 2) bounds(upper)"#,
+        lower = 1,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(record.ids, vec![1, 2]);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_as_field_splat(db: PgPool) -> Result<()> {
+    let account = Account {
+        id: 1,
+        name: Some("Herp Derpinson".to_owned()),
+    };
+
+    let copy = sqlx_named::query_as!(
+        Account,
+        r#"SELECT id "id!", name from (VALUES ($id::int4, $name::text)) accounts(id, name)"#,
+        ..&account,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(copy.id, account.id);
+    assert_eq!(copy.name, account.name);
+
+    let renamed = sqlx_named::query_as!(
+        Account,
+        r#"SELECT id "id!", name from (VALUES ($id::int4, $name::text)) accounts(id, name)"#,
+        name = "Derp Herpinson",
+        ..&account,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(renamed.name.as_deref(), Some("Derp Herpinson"));
+
+    let limited = sqlx_named::query_as!(
+        Account,
+        r#"SELECT id "id!", name from (VALUES ($id::int4, $name::text)) accounts(id, name) limit $limit"#,
+        ..&account,
+        limit ?= 1i64,
+    )
+    .fetch_all(&db)
+    .await?;
+    assert_eq!(limited.len(), 1);
+
+    Ok(())
+}
+
+#[cfg(feature = "meta")]
+#[test]
+fn test_meta() {
+    let meta: sqlx_named_core::meta::QueryMeta = sqlx_named::meta!(query!(
+        "select $name::text, $id::int, $name::text",
+        id = 1,
+        name = "name",
+    ));
+
+    assert_eq!(meta.macro_name, "query");
+    assert_eq!(
+        meta.original_sql,
+        "select $name::text, $id::int, $name::text"
+    );
+    assert_eq!(meta.variants.len(), 1);
+    assert!(meta.variants[0]
+        .sql
+        .starts_with("select $2::text, $1::int, $2::text"));
+    assert_eq!(meta.variants[0].params, &[("id", 1), ("name", 2)]);
+    assert_eq!(meta.file, file!());
+
+    let meta = sqlx_named::meta!(query_as!(
+        Account,
+        "select id from accounts where true [[ and id = $id ]]",
+        id = Some(1),
+    ));
+
+    assert_eq!(meta.variants.len(), 2);
+    assert_eq!(meta.variants[1].params, &[("id", 1)]);
+}
+
+#[cfg(all(feature = "sql-comments", feature = "meta"))]
+#[test]
+fn test_sql_comments() {
+    #[rustfmt::skip]
+    let (line, meta) = (line!(), sqlx_named::meta!(query!("select 1;")));
+
+    assert_eq!(
+        meta.variants[0].sql,
+        format!("select 1\n/*file='tests%2Fmacros.rs%3A{}'*/;", line),
+    );
+}
+
+macro_rules! checked_query_as {
+    ($($args:tt)*) => {
+        sqlx::query_as!($($args)*)
+    };
+}
+
+#[sqlx::test]
+async fn test_wrap(db: PgPool) -> Result<()> {
+    let account = sqlx_named::wrap!(
+        checked_query_as,
+        Account,
+        r#"SELECT id "id!", name from (VALUES (1, 'Herp Derpinson')) accounts(id, name) where id = $id"#,
+        id = 1,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(account.id, 1);
+
+    let name = sqlx_named::wrap!(
+        sqlx::query_scalar,
+        r#"SELECT $name::text "name!""#,
+        name = "Herp Derpinson",
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(name, "Herp Derpinson");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_column_overrides(db: PgPool) -> Result<()> {
+    let account = sqlx_named::query!(
+        "SELECT id $col:id!, name $col:name?, id $col:wrapped!: MyInt4 from (VALUES (1, 'Herp Derpinson')) accounts(id, name) where id = $id",
+        id = 1,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(account.id, 1);
+    assert_eq!(account.name.as_deref(), Some("Herp Derpinson"));
+    assert_eq!(account.wrapped, MyInt4(1));
+
+    let account = sqlx_named::query!(
+        "SELECT id $col:id! from (VALUES (1, 'Herp Derpinson')) accounts(id, name) where id = $1",
+        1,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(account.id, 1);
+
+    // only the override is rewritten, escapes and comments are passed on as written
+    let account = sqlx_named::query!(
+        r#"SELECT id $col:id!, E'a\'b' "note!" /* $col:no */ from (VALUES (1)) accounts(id) where id = $1"#,
+        1,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(account.id, 1);
+    assert_eq!(account.note, "a'b");
+
+    #[cfg(feature = "meta")]
+    {
+        let meta = sqlx_named::meta!(query!(
+            r#"SELECT 1 $col:id!, E'a\'b' "note!" /* $col:no */ where 1 = $1"#,
+            1,
+        ));
+        assert!(meta.variants[0]
+            .sql
+            .starts_with(r#"SELECT 1 "id!", E'a\'b' "note!" /* $col:no */ where 1 = $1"#));
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_escapes(db: PgPool) -> Result<()> {
+    // named queries, like the ones `sqlx-named convert` writes, are passed on as written too
+    let name = "Herp Derpinson";
+    let row = sqlx_named::query!(
+        r#"select E'a\'b' "note!", $name::text "name!" /* $2 */"#,
+        name,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(row.note, "a'b");
+    assert_eq!(row.name, name);
+
+    let flag = Some(true);
+    let note = sqlx_named::query_scalar!(
+        r#"select E'a\'b' "note!" where true [[ and $flag::bool ]]"#,
+        flag,
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(note, "a'b");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_query_bind_each(db: PgPool) -> Result<()> {
+    let mut evaluated = 0;
+    let row = sqlx_named::query!(
+        #[bind_each]
+        r#"SELECT $name::text "text!", $name::varchar "varchar!""#,
+        name = {
+            evaluated += 1;
+            "Herp Derpinson".to_owned()
+        },
+    )
+    .fetch_one(&db)
+    .await?;
+    assert_eq!(row.text, "Herp Derpinson");
+    assert_eq!(row.varchar, "Herp Derpinson");
+    assert_eq!(evaluated, 1);
+
+    #[cfg(feature = "meta")]
+    {
+        let meta = sqlx_named::meta!(query!(
+            #[bind_each]
+            "select $name::text, $name::varchar",
+            name = "x",
+        ));
+        assert!(meta.variants[0]
+            .sql
+            .starts_with("select $1::text, $2::varchar"));
+        assert_eq!(meta.variants[0].params, &[("name", 1), ("name", 2)]);
+    }
+
+    Ok(())
+}
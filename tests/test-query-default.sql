@@ -0,0 +1,2 @@
+-- $id = 1i32
+SELECT id "id!", name from (VALUES (1, 'Herp Derpinson')) accounts(id, name) where id = $id
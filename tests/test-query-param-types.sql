@@ -0,0 +1,2 @@
+-- param: id MyInt4
+SELECT id "id!", name from (VALUES (1, 'Herp Derpinson')) accounts(id, name) where id = $id
@@ -0,0 +1,8 @@
+fn main() {
+    let email = 1i32;
+    let _ = sqlx_named::query!("select $id::int4, $email::text", id = 1i32, email);
+    let _ = sqlx_named::query!("select $id::int4, $email::text", id = 1i32, email = 2i32);
+    let _ = sqlx_named::query!("select $id::int4, $limit::text", id = 1i32, limit ?= 3i32);
+    // repeated args are evaluated once, before the query
+    let _ = sqlx_named::query!("select $email::text as a, $email::text as b", email = 4i32);
+}
@@ -0,0 +1,4 @@
+fn main() {
+    let a = Some(1);
+    let _ = sqlx_named::query!("select $a::int4 where true [[ and 1 > 0 ]]", a);
+}
@@ -0,0 +1,7 @@
+fn main() {
+    let sort = "id";
+    let _ = sqlx_named::query!(
+        "select 1 from users where ${sort in [id, name, age]} is not null order by ${sort in [age, name]}",
+        sort,
+    );
+}
@@ -0,0 +1,7 @@
+fn main() {
+    let _ = sqlx_named::query!(
+        "select $user_id::int4, $email::text",
+        usr_id = 1,
+        page = 2,
+    );
+}
@@ -0,0 +1,8 @@
+fn main() {
+    let (a, b) = (Some(1), Some(2));
+    let _ = sqlx_named::query!(
+        "select 1 where true [[ and $a::int4 > 0 [[ and $b::int4 > 0 ]] ]]",
+        a,
+        b,
+    );
+}
@@ -0,0 +1,5 @@
+fn main() {
+    let id = 1i64;
+    // relative to the crate trybuild builds in target/tests/trybuild
+    let _ = sqlx_named::query_file!("../../../../tests/ui/param-type-conflict.sql", id as i64);
+}
@@ -0,0 +1,2 @@
+-- param: id i32
+select $id
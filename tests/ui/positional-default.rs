@@ -0,0 +1,3 @@
+fn main() {
+    let _ = sqlx_named::query!("select $1::int4", 1i32, limit ?= 5i64);
+}
@@ -0,0 +1,8 @@
+struct User {
+    id: i32,
+}
+
+fn main() {
+    let user = User { id: 1 };
+    let _ = sqlx_named::query!("select $id::int4, $self::int4", ..user);
+}
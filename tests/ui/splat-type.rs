@@ -0,0 +1,10 @@
+struct User {
+    id: i32,
+    email: i32,
+}
+
+fn main() {
+    let user = User { id: 1, email: 2 };
+    let _ = sqlx_named::query!("select $id::int4, $email::text", ..user);
+    let _ = sqlx_named::query!("select $id::int4, $email::text", ..user { .id, .email });
+}
@@ -0,0 +1,11 @@
+fn main() {
+    let (a, b, c, d, e) = (Some(true), Some(true), Some(true), Some(true), Some(true));
+    let _ = sqlx_named::query!(
+        "select 1 where true [[ and $a ]] [[ and $b ]] [[ and $c ]] [[ and $d ]] [[ and $e ]]",
+        a,
+        b,
+        c,
+        d,
+        e,
+    );
+}
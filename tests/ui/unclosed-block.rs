@@ -0,0 +1,4 @@
+fn main() {
+    let a = Some(1);
+    let _ = sqlx_named::query!("select 1 where true [[ and $a::int4 > 0", a);
+}
@@ -0,0 +1,4 @@
+fn main() {
+    // relative to the crate trybuild builds in target/tests/trybuild
+    let _ = sqlx_named::query_file!("../../../../tests/ui/unknown-params.sql", id = 1);
+}
@@ -0,0 +1,3 @@
+-- param: id i32
+-- param: page i64
+select $id
@@ -0,0 +1,3 @@
+fn main() {
+    let _ = sqlx_named::query!("select $id::int4", id = 1, page = 2);
+}
@@ -0,0 +1,5 @@
+#![deny(deprecated)]
+
+fn main() {
+    let _ = sqlx_named::query!(#[warn(unused_args)] "select $id::int4", id = 1, page = 2);
+}